@@ -0,0 +1,219 @@
+use std::{
+    path::PathBuf,
+    process::ExitCode,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use anyhow::{anyhow, Result};
+use clap::{ArgAction, Args};
+use codeowners_rs::{parser::LineIndex, patternset};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{parse_codeowners, plural, print_diagnostic, read_codeowners, Cli, PathArgs};
+
+#[derive(Args)]
+#[group(id = "checks", required = true, multiple = true)]
+pub(crate) struct CheckArgs {
+    #[command(flatten)]
+    path_args: PathArgs,
+
+    /// Report rules that match no files, and rules that match files but are
+    /// always overridden by later rules
+    #[arg(long, group = "checks")]
+    unused_rules: bool,
+
+    /// Report unowned files matching this pattern. Patterns use CODEOWNERS
+    /// syntax, so /src/ covers everything in the top-level src directory, and *
+    /// covers every file. May be used multiple times to require several
+    /// patterns
+    #[arg(long, action = ArgAction::Append, value_name = "PATTERN", group = "checks")]
+    require_owned: Option<Vec<String>>,
+
+    /// Fail if more than this many files are unowned. Either a number of files,
+    /// such as 10, or a percentage of all files, such as 5%
+    #[arg(long, value_name = "N|N%", group = "checks")]
+    max_unowned: Option<Threshold>,
+}
+
+#[derive(Clone, Copy)]
+enum Threshold {
+    Count(usize),
+    Percent(f64),
+}
+
+impl Threshold {
+    fn exceeded_by(&self, unowned: usize, total: usize) -> bool {
+        match *self {
+            Threshold::Count(max) => unowned > max,
+            Threshold::Percent(max) => unowned as f64 * 100.0 > max * total as f64,
+        }
+    }
+}
+
+impl FromStr for Threshold {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.strip_suffix('%') {
+            Some(percent) => percent
+                .parse::<f64>()
+                .ok()
+                .filter(|p| (0.0..=100.0).contains(p))
+                .map(Threshold::Percent)
+                .ok_or_else(|| anyhow!("expected a percentage between 0% and 100%")),
+            None => s
+                .parse()
+                .map(Threshold::Count)
+                .map_err(|_| anyhow!("expected a number of files or a percentage")),
+        }
+    }
+}
+
+impl std::fmt::Display for Threshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Threshold::Count(count) => write!(f, "{}", count),
+            Threshold::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+pub(crate) fn run(cli: &Cli, args: &CheckArgs) -> Result<ExitCode> {
+    let (codeowners_path, source) = read_codeowners(cli)?;
+    let parse_result = parse_codeowners(cli, &codeowners_path, &source);
+    let rule_spans = parse_result
+        .rules
+        .iter()
+        .map(|rule| rule.span())
+        .collect::<Vec<_>>();
+    let ruleset = parse_result.into_ruleset();
+
+    // Count how many paths each rule matches, and how many paths it's the
+    // winning rule for.
+    let matched = (0..rule_spans.len())
+        .map(|_| AtomicUsize::new(0))
+        .collect::<Vec<_>>();
+    let won = (0..rule_spans.len())
+        .map(|_| AtomicUsize::new(0))
+        .collect::<Vec<_>>();
+
+    // Count the files, and the unowned files, and collect the unowned files
+    // that must be owned.
+    let total = AtomicUsize::new(0);
+    let unowned = AtomicUsize::new(0);
+    let unowned_required = Mutex::new(Vec::new());
+    let required_matcher = args.require_owned.as_ref().map(|patterns| {
+        let mut builder = patternset::Builder::new();
+        for pattern in patterns {
+            builder.add(pattern);
+        }
+        builder.build()
+    });
+
+    let paths = args.path_args.paths_iter()?;
+    #[cfg(feature = "rayon")]
+    let paths = paths.par_bridge();
+
+    let tl = thread_local::ThreadLocal::new();
+    paths.for_each(|path| {
+        let (ruleset, required_matcher) = tl.get_or(|| (ruleset.clone(), required_matcher.clone()));
+        let path = path.strip_prefix(".").unwrap_or(&path);
+        let winners = ruleset.matching_rules(path);
+        if args.unused_rules {
+            for (idx, _) in ruleset.all_matching_rules(path) {
+                matched[idx].fetch_add(1, Ordering::Relaxed);
+            }
+            for (idx, _) in &winners {
+                won[*idx].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        // With GitLab-style sections, a path is owned if the winning rule in
+        // any section has owners
+        total.fetch_add(1, Ordering::Relaxed);
+        if winners.iter().all(|(_, rule)| rule.owners.is_empty()) {
+            unowned.fetch_add(1, Ordering::Relaxed);
+            let required = required_matcher
+                .as_ref()
+                .is_some_and(|m| !m.matching_patterns(path).is_empty());
+            if required {
+                unowned_required.lock().unwrap().push(PathBuf::from(path));
+            }
+        }
+    });
+
+    let line_index = LineIndex::new(&source);
+    let mut problems = 0;
+    for (idx, span) in rule_spans.iter().enumerate().filter(|_| args.unused_rules) {
+        let matched = matched[idx].load(Ordering::Relaxed);
+        let won = won[idx].load(Ordering::Relaxed);
+        let message = match (matched, won) {
+            (0, _) => "rule doesn't match any files".to_string(),
+            (n, 0) => format!(
+                "rule matches {} {} but is always overridden by a later rule",
+                n,
+                plural(n, "file", "files")
+            ),
+            _ => continue,
+        };
+        if problems > 0 {
+            eprintln!();
+        }
+        print_diagnostic(&codeowners_path, &source, &line_index, span, &message);
+        problems += 1;
+    }
+
+    // Unowned files that must be owned are listed after any rule diagnostics,
+    // followed by a summary of the ownership checks that failed.
+    let mut unowned_required = unowned_required.into_inner().unwrap();
+    unowned_required.sort();
+    if problems > 0 && !unowned_required.is_empty() {
+        eprintln!();
+    }
+    for path in &unowned_required {
+        eprintln!("{}: file has no owners", path.display());
+    }
+    problems += unowned_required.len();
+
+    let mut summary = Vec::new();
+    if !unowned_required.is_empty() {
+        summary.push(format!(
+            "{} {} must be owned but {} no owners",
+            unowned_required.len(),
+            plural(unowned_required.len(), "file", "files"),
+            plural(unowned_required.len(), "has", "have"),
+        ));
+    }
+    let (total, unowned) = (total.into_inner(), unowned.into_inner());
+    if let Some(max_unowned) = args.max_unowned {
+        if max_unowned.exceeded_by(unowned, total) {
+            summary.push(format!(
+                "{} of {} {} {} unowned ({:.1}%), more than the maximum of {}",
+                unowned,
+                total,
+                plural(total, "file", "files"),
+                plural(unowned, "is", "are"),
+                unowned as f64 * 100.0 / total as f64,
+                max_unowned
+            ));
+        }
+    }
+    if !summary.is_empty() {
+        if problems > 0 {
+            eprintln!();
+        }
+        for line in &summary {
+            eprintln!("error: {}", line);
+        }
+    }
+
+    if problems > 0 || !summary.is_empty() {
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,130 @@
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use crate::{git, output, parse_codeowners, plural, Cli};
+
+#[derive(Args)]
+pub(crate) struct DiffArgs {
+    /// The base revision. Owners are taken from the CODEOWNERS file as it
+    /// exists at this revision
+    base: String,
+
+    /// The head revision
+    head: String,
+}
+
+pub(crate) fn run(cli: &Cli, args: &DiffArgs) -> Result<()> {
+    let base = git::resolve_commit(&args.base)?;
+    let head = git::resolve_commit(&args.head)?;
+
+    // Changed paths are relative to the root of the repository, as are the
+    // patterns in the CODEOWNERS file, so both are resolved against the root
+    // rather than the current directory.
+    let root = git::toplevel()?;
+    let changed_files = git::changed_files(&base, &head)?;
+    let (codeowners_path, source) = read_codeowners_at(cli, &root, &args.base, &base)?;
+    let ruleset = parse_codeowners(cli, &codeowners_path, &source).into_ruleset();
+
+    let mut stdout = std::io::stdout().lock();
+    let mut files_per_owner = BTreeMap::new();
+    let mut unowned = 0;
+    for path in &changed_files {
+        let owners = ruleset.owners(path);
+        stdout.write_all(output::text_record(path, owners).as_bytes())?;
+        match owners {
+            Some(owners) => {
+                for owner in owners {
+                    *files_per_owner.entry(owner.value.as_str()).or_insert(0) += 1;
+                }
+            }
+            None => unowned += 1,
+        }
+    }
+
+    if changed_files.is_empty() {
+        return Ok(());
+    }
+    writeln!(stdout)?;
+    writeln!(stdout, "Owners:")?;
+    let width = files_per_owner.keys().map(|o| o.len()).max().unwrap_or(0);
+    for (owner, count) in &files_per_owner {
+        writeln!(
+            stdout,
+            "  {:<width$}  {} {}",
+            owner,
+            count,
+            plural(*count, "file", "files")
+        )?;
+    }
+    if files_per_owner.is_empty() {
+        writeln!(stdout, "  (none)")?;
+    }
+    if unowned > 0 {
+        writeln!(
+            stdout,
+            "Unowned: {} {}",
+            unowned,
+            plural(unowned, "file", "files")
+        )?;
+    }
+    Ok(())
+}
+
+// Read the CODEOWNERS file as it exists at the given commit. Exits if no
+// CODEOWNERS file could be found at that commit.
+fn read_codeowners_at(
+    cli: &Cli,
+    root: &Path,
+    rev: &str,
+    commit: &str,
+) -> Result<(PathBuf, String)> {
+    let candidates = match &cli.codeowners_file {
+        // A path given on the command line is relative to the current
+        // directory, so it needs to be made relative to the root
+        Some(path) => {
+            let path = normalize(&std::env::current_dir()?.join(path));
+            let path = path.strip_prefix(root).map_err(|_| {
+                anyhow!(
+                    "{} is outside the repository at {}",
+                    path.display(),
+                    root.display()
+                )
+            })?;
+            vec![path.to_path_buf()]
+        }
+        None => Cli::DEFAULT_PATHS
+            .iter()
+            .map(|path| Path::new(path).strip_prefix(".").unwrap_or(Path::new(path)))
+            .map(PathBuf::from)
+            .collect(),
+    };
+    for path in candidates {
+        if let Some(source) = git::file_at_revision(commit, &path)? {
+            return Ok((PathBuf::from(format!("{}:{}", rev, path.display())), source));
+        }
+    }
+    eprintln!("error: no CODEOWNERS file found at {}", rev);
+    std::process::exit(1);
+}
+
+// Remove `.` and `..` components from an absolute path without touching the
+// filesystem, as the path may not exist in the working tree.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
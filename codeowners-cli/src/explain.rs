@@ -0,0 +1,140 @@
+use std::{io::Write, path::PathBuf};
+
+use anyhow::Result;
+use clap::Args;
+use codeowners_rs::{parser::LineIndex, patternset::TransitionKind, Owner};
+
+use crate::{parse_codeowners, read_codeowners, Cli};
+
+#[derive(Args)]
+pub(crate) struct ExplainArgs {
+    /// The path to explain, relative to the root of the repository
+    path: PathBuf,
+}
+
+pub(crate) fn run(cli: &Cli, args: &ExplainArgs) -> Result<()> {
+    let (codeowners_path, source) = read_codeowners(cli)?;
+    let parse_result = parse_codeowners(cli, &codeowners_path, &source);
+    let rule_spans = parse_result
+        .rules
+        .iter()
+        .map(|rule| rule.span())
+        .collect::<Vec<_>>();
+    let ruleset = parse_result.into_ruleset();
+    let line_index = LineIndex::new(&source);
+
+    let path = args.path.strip_prefix(".").unwrap_or(&args.path);
+    let components = path
+        .iter()
+        .map(|c| c.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let matches = ruleset.trace_matching_rules(path);
+    // With GitLab-style sections, each section has its own winning rule
+    let winners = ruleset
+        .matching_rules(path)
+        .into_iter()
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+
+    let mut stdout = std::io::stdout().lock();
+    if matches.is_empty() {
+        writeln!(stdout, "No rules match {}", path.display())?;
+        writeln!(stdout, "\nOwners: (unowned)")?;
+        return Ok(());
+    }
+
+    let width = components.iter().map(|c| c.len()).max().unwrap_or(0);
+    for (_, trace) in &matches {
+        let span = &rule_spans[trace.pattern];
+        let line = span.start_position(&line_index).line;
+        let winner = if winners.contains(&trace.pattern) {
+            "  (wins)"
+        } else {
+            ""
+        };
+        writeln!(
+            stdout,
+            "line {}: {}{}",
+            line,
+            &source[span.0..span.1],
+            winner
+        )?;
+
+        // Epsilon steps don't consume path components, so they're skipped
+        for step in &trace.steps {
+            let Some(component) = step.component else {
+                continue;
+            };
+            writeln!(
+                stdout,
+                "  {:<width$}  matched by  {:<12} ({})",
+                components[component],
+                step.segment,
+                kind_name(step.kind),
+            )?;
+        }
+        writeln!(stdout)?;
+    }
+
+    // Each section contributes its own owners
+    if !ruleset.sections().is_empty() {
+        writeln!(stdout, "Owners:")?;
+        let section_matches = ruleset.section_matches(path);
+        let names = section_matches
+            .iter()
+            .map(|m| match m.section {
+                Some(section) if section.optional => format!("^[{}]", section.name),
+                Some(section) => format!("[{}]", section.name),
+                None => "(no section)".to_string(),
+            })
+            .collect::<Vec<_>>();
+        let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+        for (section_match, name) in section_matches.iter().zip(&names) {
+            let line = rule_spans[section_match.rule_index]
+                .start_position(&line_index)
+                .line;
+            writeln!(
+                stdout,
+                "  {:<width$}  {} (from line {})",
+                name,
+                format_owners(&section_match.rule.owners),
+                line
+            )?;
+        }
+        return Ok(());
+    }
+
+    // The last matching rule wins
+    let (rule, trace) = &matches[matches.len() - 1];
+    let line = rule_spans[trace.pattern].start_position(&line_index).line;
+    writeln!(
+        stdout,
+        "Owners: {} (from line {}, the last matching rule)",
+        format_owners(&rule.owners),
+        line
+    )?;
+    Ok(())
+}
+
+fn format_owners(owners: &[Owner]) -> String {
+    match owners {
+        [] => "(unowned)".to_string(),
+        owners => owners
+            .iter()
+            .map(|o| o.value.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+fn kind_name(kind: TransitionKind) -> &'static str {
+    match kind {
+        TransitionKind::Literal => "literal",
+        TransitionKind::Prefix => "prefix",
+        TransitionKind::Suffix => "suffix",
+        TransitionKind::Contains => "contains",
+        TransitionKind::Regex => "glob",
+        TransitionKind::Unconditional => "any",
+        TransitionKind::Epsilon => "epsilon",
+    }
+}
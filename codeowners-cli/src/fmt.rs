@@ -0,0 +1,46 @@
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use clap::Args;
+use codeowners_rs::parser::{self, FormatOptions};
+
+use crate::{parse_codeowners, read_codeowners, Cli};
+
+#[derive(Args)]
+pub(crate) struct FmtArgs {
+    /// Check whether the file is formatted rather than rewriting it. Exits with a
+    /// non-zero status if formatting would change the file
+    #[arg(long)]
+    check: bool,
+
+    /// Sort the owners of each rule alphabetically
+    #[arg(long)]
+    sort_owners: bool,
+
+    /// Separate owners with a single space rather than aligning them into columns
+    #[arg(long)]
+    no_align: bool,
+}
+
+pub(crate) fn run(cli: &Cli, args: &FmtArgs) -> Result<ExitCode> {
+    let (codeowners_path, source) = read_codeowners(cli)?;
+    let parse_result = parse_codeowners(cli, &codeowners_path, &source);
+
+    let options = FormatOptions {
+        align_owners: !args.no_align,
+        sort_owners: args.sort_owners,
+    };
+    let formatted = parser::format(&parse_result, &source, &options);
+    if formatted == source {
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if args.check {
+        eprintln!("{} is not formatted", codeowners_path.display());
+        return Ok(ExitCode::FAILURE);
+    }
+
+    std::fs::write(&codeowners_path, formatted)
+        .with_context(|| format!("writing {:?}", codeowners_path))?;
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,540 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+// Find the git directory for the repository rooted at `root`. This is usually
+// `root/.git`, but in linked worktrees and submodules `.git` is a file
+// containing the path to the real git directory.
+pub(crate) fn git_dir(root: &Path) -> Result<PathBuf> {
+    let dot_git = root.join(".git");
+    if dot_git.is_dir() {
+        return Ok(dot_git);
+    }
+    let contents = fs::read_to_string(&dot_git)
+        .with_context(|| format!("{} is not the root of a git repository", root.display()))?;
+    let gitdir = contents
+        .strip_prefix("gitdir:")
+        .ok_or_else(|| anyhow!("{}: expected a gitdir line", dot_git.display()))?
+        .trim();
+    Ok(root.join(gitdir))
+}
+
+// Return the paths of all files tracked in the git index of the repository
+// rooted at `root`, relative to `root`. The index is read directly rather than
+// shelling out to `git ls-files`.
+pub(crate) fn tracked_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let git_dir = git_dir(root)?;
+    let index_path = git_dir.join("index");
+    let data = match fs::read(&index_path) {
+        Ok(data) => data,
+        // There's no index until something has been added to the repository
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err).with_context(|| format!("reading {}", index_path.display())),
+    };
+    parse_index(&data, oid_len(&git_dir))
+        .with_context(|| format!("reading {}", index_path.display()))
+}
+
+// Return the paths of all files tracked in the git index that are within
+// `dir`, relative to `dir`. `dir` must be absolute, and may be anywhere in the
+// working tree of a repository.
+pub(crate) fn tracked_files_within(dir: &Path) -> Result<Vec<PathBuf>> {
+    let root = dir
+        .ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .ok_or_else(|| anyhow!("{} is not in a git repository", dir.display()))?;
+    let prefix = dir.strip_prefix(root).expect("root is an ancestor of dir");
+    Ok(tracked_files(root)?
+        .into_iter()
+        .filter_map(|path| path.strip_prefix(prefix).ok().map(Path::to_path_buf))
+        .collect())
+}
+
+// Return the root of the working tree of the repository containing the current
+// directory.
+pub(crate) fn toplevel() -> Result<PathBuf> {
+    let output = run(&["rev-parse", "--show-toplevel"])?;
+    let output = String::from_utf8(output).context("reading git rev-parse output")?;
+    Ok(PathBuf::from(output.trim_end_matches('\n')))
+}
+
+// Resolve a revision to the id of the commit it refers to. Revisions come from
+// the user, so they're passed after `--end-of-options` to stop ones starting
+// with `-` being taken as options.
+pub(crate) fn resolve_commit(rev: &str) -> Result<String> {
+    let spec = format!("{}^{{commit}}", rev);
+    let output = run(&["rev-parse", "--verify", "--end-of-options", &spec])
+        .with_context(|| format!("{} is not a valid revision", rev))?;
+    Ok(String::from_utf8_lossy(&output).trim().to_string())
+}
+
+// Return the paths of files that differ between two commits, relative to the
+// root of the repository. Renames are reported as a deletion of the old path
+// and an addition of the new one, since both paths need review.
+pub(crate) fn changed_files(base: &str, head: &str) -> Result<Vec<PathBuf>> {
+    let output = run(&[
+        "diff",
+        "--name-only",
+        "--no-renames",
+        "-z",
+        "--end-of-options",
+        base,
+        head,
+        "--",
+    ])?;
+    output
+        .split(|&b| b == 0)
+        .filter(|path| !path.is_empty())
+        .map(path_from_bytes)
+        .collect()
+}
+
+// Return the contents of a file at the given commit, or None if the file
+// doesn't exist at that commit. `path` is relative to the root of the
+// repository.
+pub(crate) fn file_at_revision(rev: &str, path: &Path) -> Result<Option<String>> {
+    let object = format!("{}:{}", rev, path.display());
+    let exists = Command::new("git")
+        .args(["cat-file", "-e", "--end-of-options", &object])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .context("running git")?
+        .success();
+    if !exists {
+        return Ok(None);
+    }
+    let contents = run(&["cat-file", "blob", "--end-of-options", &object])?;
+    String::from_utf8(contents)
+        .map(Some)
+        .with_context(|| format!("reading {}", object))
+}
+
+// Run git with the given arguments, returning its output. Fails with git's
+// error message if it exits unsuccessfully.
+fn run(args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("running git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+// The length of object ids in the repository, which depends on whether it uses
+// SHA-1 (the default) or SHA-256.
+fn oid_len(git_dir: &Path) -> usize {
+    let config = fs::read_to_string(common_dir(git_dir).join("config")).unwrap_or_default();
+    let sha256 = config.lines().any(|line| {
+        let line = line.trim().to_ascii_lowercase();
+        line.starts_with("objectformat") && line.ends_with("sha256")
+    });
+    if sha256 {
+        32
+    } else {
+        20
+    }
+}
+
+// Find the directory holding the files shared by all of a repository's
+// worktrees, such as its config. Linked worktrees have their own git directory
+// for the index and HEAD, with a `commondir` file pointing at the shared one.
+fn common_dir(git_dir: &Path) -> PathBuf {
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(contents) => git_dir.join(contents.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+const FLAG_EXTENDED: u16 = 0x4000;
+const MODE_TYPE_MASK: u32 = 0o170000;
+const MODE_DIRECTORY: u32 = 0o040000;
+
+// Parse the entries from a git index file. Supports versions 2 to 4 of the
+// format, described in git's Documentation/gitformat-index.txt.
+fn parse_index(data: &[u8], oid_len: usize) -> Result<Vec<PathBuf>> {
+    let mut reader = Reader { data, pos: 0 };
+    if reader.take(4)? != b"DIRC" {
+        bail!("not a git index file");
+    }
+    let version = reader.u32()?;
+    if !(2..=4).contains(&version) {
+        bail!("unsupported index version {}", version);
+    }
+    let entry_count = reader.u32()? as usize;
+
+    // Every entry takes at least one byte, so a corrupt entry count can't make
+    // us allocate more than the size of the file.
+    let mut paths = Vec::with_capacity(entry_count.min(reader.remaining()));
+    let mut prev_path: Vec<u8> = Vec::new();
+    for _ in 0..entry_count {
+        let entry_start = reader.pos;
+        // ctime, mtime, dev and ino
+        reader.take(24)?;
+        let mode = reader.u32()?;
+        // uid, gid, size and object id
+        reader.take(12 + oid_len)?;
+        let flags = reader.u16()?;
+        if version >= 3 && flags & FLAG_EXTENDED != 0 {
+            reader.u16()?;
+        }
+
+        let path = if version == 4 {
+            // Paths are prefix-compressed against the previous entry's path,
+            // and entries aren't padded.
+            let strip = reader.varint()?;
+            if strip > prev_path.len() {
+                bail!("corrupt path in index entry");
+            }
+            let mut path = prev_path[..prev_path.len() - strip].to_vec();
+            path.extend_from_slice(reader.nul_terminated()?);
+            path
+        } else {
+            let path = reader.nul_terminated()?.to_vec();
+            // Entries are padded with NULs to a multiple of eight bytes. One
+            // NUL has already been consumed as the path terminator.
+            let len = reader.pos - entry_start;
+            reader.take((8 - len % 8) % 8)?;
+            path
+        };
+
+        if mode & MODE_TYPE_MASK == MODE_DIRECTORY {
+            bail!("sparse indexes aren't supported");
+        }
+
+        // Conflicted files have an entry per stage. These are adjacent, as
+        // entries are sorted by path, so the duplicates are removed below.
+        paths.push(path_from_bytes(&path)?);
+        prev_path = path;
+    }
+
+    // A split index only contains the entries that differ from a shared
+    // index, so its entries alone aren't the full list of files.
+    while reader.remaining() > oid_len {
+        let signature = reader.take(4)?;
+        if signature == b"link" {
+            bail!("split indexes aren't supported");
+        }
+        let len = reader.u32()? as usize;
+        reader.take(len)?;
+    }
+
+    paths.dedup();
+    Ok(paths)
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> Result<PathBuf> {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+    Ok(PathBuf::from(OsStr::from_bytes(bytes)))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> Result<PathBuf> {
+    let path = std::str::from_utf8(bytes).context("non-UTF-8 path in index")?;
+    Ok(PathBuf::from(path))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.remaining() {
+            bail!("unexpected end of file");
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    // Read a variable-length integer in git's offset encoding, where each
+    // continuation adds one before shifting, so encodings are unique. Fails if
+    // the value doesn't fit in a usize, which can only happen if the index is
+    // corrupt.
+    fn varint(&mut self) -> Result<usize> {
+        let mut byte = self.take(1)?[0];
+        let mut value = (byte & 0x7f) as usize;
+        while byte & 0x80 != 0 {
+            byte = self.take(1)?[0];
+            value = value
+                .checked_add(1)
+                .and_then(|v| v.checked_mul(1 << 7))
+                .ok_or_else(|| anyhow!("corrupt variable-length integer"))?
+                | (byte & 0x7f) as usize;
+        }
+        Ok(value)
+    }
+
+    // Read up to and including the next NUL byte, returning the bytes before it.
+    fn nul_terminated(&mut self) -> Result<&'a [u8]> {
+        let len = self.data[self.pos..]
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| anyhow!("unexpected end of file"))?;
+        let bytes = self.take(len)?;
+        self.pos += 1;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OID_LEN: usize = 20;
+
+    struct Entry {
+        path: &'static str,
+        mode: u32,
+        stage: u16,
+        extended_flags: Option<u16>,
+    }
+
+    fn entry(path: &'static str) -> Entry {
+        Entry {
+            path,
+            mode: 0o100644,
+            stage: 0,
+            extended_flags: None,
+        }
+    }
+
+    // Encode an index file with the given entries, followed by `extensions`
+    // and a (zeroed) checksum.
+    fn index(version: u32, entries: &[Entry], extensions: &[u8]) -> Vec<u8> {
+        let mut data = b"DIRC".to_vec();
+        data.extend(version.to_be_bytes());
+        data.extend((entries.len() as u32).to_be_bytes());
+        let mut prev_path = "";
+        for entry in entries {
+            let start = data.len();
+            data.extend([0; 24]);
+            data.extend(entry.mode.to_be_bytes());
+            data.extend([0; 12 + OID_LEN]);
+            let mut flags = entry.stage << 12 | entry.path.len() as u16;
+            if entry.extended_flags.is_some() {
+                flags |= FLAG_EXTENDED;
+            }
+            data.extend(flags.to_be_bytes());
+            if let Some(extended_flags) = entry.extended_flags {
+                data.extend(extended_flags.to_be_bytes());
+            }
+            if version == 4 {
+                let common = prev_path
+                    .bytes()
+                    .zip(entry.path.bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                data.extend(varint(prev_path.len() - common));
+                data.extend(&entry.path.as_bytes()[common..]);
+                data.push(0);
+            } else {
+                data.extend(entry.path.as_bytes());
+                data.push(0);
+                let len = data.len() - start;
+                data.resize(data.len() + (8 - len % 8) % 8, 0);
+            }
+            prev_path = entry.path;
+        }
+        data.extend(extensions);
+        data.extend([0; OID_LEN]);
+        data
+    }
+
+    // Encode a variable-length integer the way git does.
+    fn varint(mut value: usize) -> Vec<u8> {
+        let mut bytes = vec![(value & 0x7f) as u8];
+        value >>= 7;
+        while value != 0 {
+            value -= 1;
+            bytes.push(0x80 | (value & 0x7f) as u8);
+            value >>= 7;
+        }
+        bytes.reverse();
+        bytes
+    }
+
+    fn parse(data: &[u8]) -> Result<Vec<String>> {
+        Ok(parse_index(data, OID_LEN)?
+            .into_iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect())
+    }
+
+    #[test]
+    fn test_padding() {
+        // The fixed part of an entry is 62 bytes, so these paths need 1, 8
+        // and 2 NULs to pad their entries to a multiple of eight bytes
+        let data = index(2, &[entry("a"), entry("ab"), entry("src/a.rs")], &[]);
+        assert_eq!(parse(&data).unwrap(), ["a", "ab", "src/a.rs"]);
+    }
+
+    #[test]
+    fn test_extended_flags() {
+        let intent_to_add = Entry {
+            extended_flags: Some(0x2000),
+            ..entry("new.rs")
+        };
+        let data = index(3, &[entry("a.rs"), intent_to_add, entry("z.rs")], &[]);
+        assert_eq!(parse(&data).unwrap(), ["a.rs", "new.rs", "z.rs"]);
+    }
+
+    #[test]
+    fn test_prefix_compression() {
+        let entries = [
+            entry("src/a.rs"),
+            entry("src/b/c.rs"),
+            entry("src/b/d.rs"),
+            entry("z"),
+        ];
+        let data = index(4, &entries, &[]);
+        assert_eq!(
+            parse(&data).unwrap(),
+            ["src/a.rs", "src/b/c.rs", "src/b/d.rs", "z"]
+        );
+
+        // Stripping more than the previous path is an error
+        let mut data = index(4, &[entry("a")], &[]);
+        data[12 + 62] = 2;
+        assert!(parse(&data).is_err());
+    }
+
+    #[test]
+    fn test_varint() {
+        for value in [0, 1, 127, 128, 255, 16511, 16512, 1 << 40, usize::MAX] {
+            let bytes = varint(value);
+            let mut reader = Reader {
+                data: &bytes,
+                pos: 0,
+            };
+            assert_eq!(reader.varint().unwrap(), value, "{:?}", bytes);
+            assert_eq!(reader.remaining(), 0);
+        }
+        assert_eq!(varint(128), [0x80, 0x00]);
+
+        // Truncated and overflowing integers are errors rather than panics
+        for bytes in [&[0x80][..], &[0xff; 16][..]] {
+            let mut reader = Reader {
+                data: bytes,
+                pos: 0,
+            };
+            assert!(reader.varint().is_err(), "{:?}", bytes);
+        }
+    }
+
+    #[test]
+    fn test_conflict_stages() {
+        let stage = |path, stage| Entry {
+            stage,
+            ..entry(path)
+        };
+        let entries = [stage("a", 1), stage("a", 2), stage("a", 3), entry("b")];
+        assert_eq!(parse(&index(2, &entries, &[])).unwrap(), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_unsupported_indexes() {
+        let sparse_dir = Entry {
+            mode: MODE_DIRECTORY,
+            ..entry("docs/")
+        };
+        let err = parse(&index(2, &[entry("a"), sparse_dir], &[])).unwrap_err();
+        assert!(err.to_string().contains("sparse"), "{}", err);
+
+        let mut link = b"link".to_vec();
+        link.extend((OID_LEN as u32).to_be_bytes());
+        link.extend([0; OID_LEN]);
+        let err = parse(&index(2, &[entry("a")], &link)).unwrap_err();
+        assert!(err.to_string().contains("split"), "{}", err);
+
+        // Other extensions are skipped
+        let mut tree = b"TREE".to_vec();
+        tree.extend(3u32.to_be_bytes());
+        tree.extend(b"abc");
+        assert_eq!(parse(&index(2, &[entry("a")], &tree)).unwrap(), ["a"]);
+
+        assert!(parse(&index(5, &[], &[])).is_err());
+        assert!(parse(b"DIRX\0\0\0\x02\0\0\0\0").is_err());
+    }
+
+    #[test]
+    fn test_truncated() {
+        for version in 2..=4 {
+            let data = index(version, &[entry("src/a.rs"), entry("src/b.rs")], &[]);
+            for len in 0..data.len() - OID_LEN {
+                assert!(parse(&data[..len]).is_err(), "v{} at {}", version, len);
+            }
+        }
+
+        // A corrupt entry count fails without allocating space for it
+        let mut data = index(2, &[entry("a")], &[]);
+        data[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(parse(&data).is_err());
+    }
+
+    #[test]
+    fn test_worktree_oid_len() {
+        let root = std::env::temp_dir().join(format!("codeowners-git-{}", std::process::id()));
+        let common = root.join("repo.git");
+        let worktree = common.join("worktrees").join("wt");
+        fs::create_dir_all(&worktree).unwrap();
+        fs::write(
+            common.join("config"),
+            "[extensions]\n\tobjectFormat = sha256\n",
+        )
+        .unwrap();
+        fs::write(worktree.join("commondir"), "../..\n").unwrap();
+
+        assert_eq!(oid_len(&common), 32);
+        assert_eq!(oid_len(&worktree), 32);
+        assert_eq!(oid_len(&root), 20);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_tracked_files_within() {
+        let root = std::env::temp_dir().join(format!("codeowners-index-{}", std::process::id()));
+        let subdir = root.join("src").join("bin");
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(&subdir).unwrap();
+        let entries = [
+            entry("README.md"),
+            entry("src/bin/main.rs"),
+            entry("src/lib.rs"),
+        ];
+        fs::write(root.join(".git").join("index"), index(2, &entries, &[])).unwrap();
+
+        // Paths are relative to the given directory, and files outside it are
+        // left out
+        let files = tracked_files_within(&subdir).unwrap();
+        assert_eq!(files, [PathBuf::from("main.rs")]);
+        assert_eq!(tracked_files_within(&root).unwrap().len(), 3);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
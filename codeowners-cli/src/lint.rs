@@ -0,0 +1,75 @@
+use std::process::ExitCode;
+
+use anyhow::Result;
+use codeowners_rs::parser::{self, Dialect, LineIndex, Severity};
+
+use crate::{parse_codeowners, plural, print_diagnostic, read_codeowners, Cli};
+
+pub(crate) fn run(cli: &Cli) -> Result<ExitCode> {
+    let (codeowners_path, source) = read_codeowners(cli)?;
+    let parse_result = parse_codeowners(cli, &codeowners_path, &source);
+    // The validator checks for syntax GitHub doesn't support, which doesn't
+    // apply to other dialects
+    let diagnostics = match cli.parse_options().dialect {
+        Dialect::GitHub => parser::validate(&parse_result, &source),
+        Dialect::GitLab => Vec::new(),
+    };
+    let rule_spans = parse_result
+        .rules
+        .iter()
+        .map(|rule| rule.span())
+        .collect::<Vec<_>>();
+    let ruleset = parse_result.into_ruleset();
+    let line_index = LineIndex::new(&source);
+
+    // Syntax GitHub doesn't support is reported first, in source order, when
+    // linting GitHub CODEOWNERS files, followed by shadowed rules.
+    let mut problems = 0;
+    for diagnostic in &diagnostics {
+        let severity = match diagnostic.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        if problems > 0 {
+            eprintln!();
+        }
+        print_diagnostic(
+            &codeowners_path,
+            &source,
+            &line_index,
+            &diagnostic.span,
+            &format!("{}: {}", severity, diagnostic.message),
+        );
+        problems += 1;
+    }
+
+    let shadowed = ruleset.shadowed_rules();
+    for (rule_idx, shadowed_by) in &shadowed {
+        let lines = shadowed_by
+            .iter()
+            .map(|&idx| rule_spans[idx].start_position(&line_index).line.to_string())
+            .collect::<Vec<_>>();
+        let message = format!(
+            "rule never applies as it's shadowed by the {} on {} {}",
+            plural(lines.len(), "rule", "rules"),
+            plural(lines.len(), "line", "lines"),
+            lines.join(", "),
+        );
+        if problems > 0 {
+            eprintln!();
+        }
+        print_diagnostic(
+            &codeowners_path,
+            &source,
+            &line_index,
+            &rule_spans[*rule_idx],
+            &message,
+        );
+        problems += 1;
+    }
+
+    if problems > 0 {
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
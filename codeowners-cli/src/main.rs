@@ -1,29 +1,57 @@
 use std::{
+    collections::HashSet,
     fs::File,
-    io::{BufRead, Read, Write},
+    io::{BufRead, Read},
     path::{Path, PathBuf},
+    process::ExitCode,
 };
 
 use anyhow::{anyhow, Context, Result};
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 #[cfg(feature = "rayon")]
 use rayon::prelude::*;
 
-use codeowners_rs::{self, Owner, RuleSet};
+use codeowners_rs::{
+    self,
+    parser::{Dialect, LineIndex, ParseError, ParseOptions, ParseResult, Span},
+    OwnerPolicy, Rule, RuleSet,
+};
+use output::{OutputFormat, RuleLocation};
+
+mod check;
+mod diff;
+mod explain;
+mod fmt;
+mod git;
+mod lint;
+mod output;
+mod owned_by;
+mod reviewers;
+mod stats;
 
 #[derive(Parser)]
-#[command(version)]
+#[command(version, args_conflicts_with_subcommands = true)]
 struct Cli {
-    paths: Vec<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    path_args: PathArgs,
 
     /// Path to a CODEOWNERS file. If omitted, the following locations will be tried:
     /// ./CODEOWNERS, ./.github/CODEOWNERS
-    #[arg(short = 'f', long = "file")]
+    #[arg(short = 'f', long = "file", global = true)]
     codeowners_file: Option<PathBuf>,
 
-    /// Match paths from this file rather than walking the directory tree
-    #[arg(short = 'p', long = "paths-from")]
-    paths_from_file: Option<PathBuf>,
+    /// The CODEOWNERS syntax to parse. The gitlab dialect adds section headers
+    /// such as [Section] and ^[Optional Section][2] @owner
+    #[arg(long, value_enum, default_value_t = DialectArg::Github, global = true)]
+    dialect: DialectArg,
+
+    /// Which owners are valid. Defaults to gitlab with --dialect gitlab, and
+    /// github otherwise
+    #[arg(long, value_enum, global = true)]
+    owner_policy: Option<OwnerPolicyArg>,
 
     /// Filter results to files owned by this owner. May be used multiple times to
     /// match multiple owners
@@ -34,28 +62,105 @@ struct Cli {
     #[arg(short = 'u', long = "unowned")]
     unowned: bool,
 
+    /// Output format. The json and ndjson formats include each file's owners
+    /// along with the pattern and location of the matching rule
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// With csv or tsv output, write a row for each of a file's owners rather
+    /// than a single row per file with the owners separated by spaces
+    #[arg(long)]
+    row_per_owner: bool,
+
     /// Concurrency. If set to 0, a sensible value based on CPU count will be used.
-    #[arg(short = 't', long = "threads", default_value_t = 0)]
+    #[arg(short = 't', long = "threads", default_value_t = 0, global = true)]
     threads: usize,
+}
 
-    #[cfg(debug_assertions)]
-    #[arg(long)]
-    all_matching_rules: bool,
+#[derive(Subcommand)]
+enum Command {
+    /// Format the CODEOWNERS file in place
+    Fmt(fmt::FmtArgs),
+    /// Check the CODEOWNERS file for rules that never apply and, with the
+    /// github dialect, syntax GitHub doesn't support
+    Lint,
+    /// Check the CODEOWNERS file against the files in the repository
+    Check(check::CheckArgs),
+    /// Show the owners of the files changed between two git revisions
+    Diff(diff::DiffArgs),
+    /// Suggest a small set of reviewers whose approval covers the given files
+    Reviewers(reviewers::ReviewersArgs),
+    /// List the files owned by an owner, or the rules that mention it
+    OwnedBy(owned_by::OwnedByArgs),
+    /// Summarise ownership coverage per owner and per top-level directory
+    Stats(stats::StatsArgs),
+    /// Show every rule matching a path, and which of them determines its owners
+    Explain(explain::ExplainArgs),
 }
 
-impl Cli {
-    const DEFAULT_PATHS: &'static [&'static str] = &["./CODEOWNERS", ".github/CODEOWNERS"];
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DialectArg {
+    /// GitHub's CODEOWNERS syntax
+    Github,
+    /// GitLab's CODEOWNERS syntax, with section headers
+    Gitlab,
+}
 
-    fn codeowners_path(&self) -> Option<PathBuf> {
-        match &self.codeowners_file {
-            Some(path) => Some(path.clone()),
-            None => Self::DEFAULT_PATHS
-                .iter()
-                .map(PathBuf::from)
-                .find(|p| p.exists()),
+impl From<DialectArg> for Dialect {
+    fn from(dialect: DialectArg) -> Self {
+        match dialect {
+            DialectArg::Github => Dialect::GitHub,
+            DialectArg::Gitlab => Dialect::GitLab,
         }
     }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OwnerPolicyArg {
+    /// GitHub usernames such as @octocat and teams such as @org/team
+    Github,
+    /// GitHub Enterprise Server usernames and teams, which may also contain
+    /// dots and underscores
+    GithubEnterprise,
+    /// GitLab usernames and groups, which may be nested
+    Gitlab,
+}
 
+impl From<OwnerPolicyArg> for OwnerPolicy {
+    fn from(policy: OwnerPolicyArg) -> Self {
+        match policy {
+            OwnerPolicyArg::Github => OwnerPolicy::GitHub,
+            OwnerPolicyArg::GithubEnterprise => OwnerPolicy::GitHubEnterprise,
+            OwnerPolicyArg::Gitlab => OwnerPolicy::GitLab,
+        }
+    }
+}
+
+// Arguments for selecting which paths to match against the CODEOWNERS file.
+#[derive(Args)]
+struct PathArgs {
+    paths: Vec<PathBuf>,
+
+    /// Match paths from this file rather than walking the directory tree
+    #[arg(short = 'p', long = "paths-from")]
+    paths_from_file: Option<PathBuf>,
+
+    /// Only match files tracked by git. The file list is read from the index of
+    /// the repository containing the current directory
+    #[arg(
+        long = "tracked-only",
+        visible_alias = "git",
+        conflicts_with_all = ["paths_from_file", "gitignore"]
+    )]
+    tracked_only: bool,
+
+    /// Skip files ignored by .gitignore files, .git/info/exclude or the global
+    /// git excludes file when walking the directory tree
+    #[arg(long, conflicts_with = "paths_from_file")]
+    gitignore: bool,
+}
+
+impl PathArgs {
     fn root_paths(&self) -> Vec<PathBuf> {
         if self.paths.is_empty() {
             vec![PathBuf::from(".")]
@@ -65,51 +170,128 @@ impl Cli {
     }
 
     // Return an iterator over all files to be checked. If --paths-from is set,
-    // return an iterator over the paths in that file. Otherwise, return an
-    // iterator over all files in the root paths. If multiple root paths are
-    // given, the iterator will return files from all of them.
+    // return an iterator over the paths in that file. If --tracked-only is set,
+    // return an iterator over the files in the git index that are within the
+    // root paths. Otherwise, return an iterator over all files in the root
+    // paths. If multiple root paths are given, the iterator will return files
+    // from all of them.
     fn paths_iter(&self) -> Result<Box<dyn Iterator<Item = PathBuf> + Send>> {
         if let Some(paths_from_file) = &self.paths_from_file {
             let file = File::open(paths_from_file)
                 .map_err(|e| anyhow!("reading {:?}: {}", paths_from_file, e))?;
             let reader = std::io::BufReader::new(file);
-            Ok(Box::new(
-                reader.lines().filter_map(|l| l.ok()).map(PathBuf::from),
-            ))
+            Ok(Box::new(read_paths(reader, paths_from_file.clone())))
+        } else if self.tracked_only {
+            let root_paths = self
+                .root_paths()
+                .into_iter()
+                .map(|root| {
+                    root.strip_prefix(".")
+                        .map(Path::to_path_buf)
+                        .unwrap_or(root)
+                })
+                .collect::<Vec<_>>();
+            let files = git::tracked_files_within(&std::env::current_dir()?)?;
+            Ok(Box::new(files.into_iter().filter(move |path| {
+                root_paths.iter().any(|root| path.starts_with(root))
+            })))
         } else {
-            Ok(self.root_paths().into_iter().map(walk_files).fold(
-                Box::new(std::iter::empty()) as Box<dyn Iterator<Item = _> + Send>,
-                |a, b| Box::new(a.chain(b)),
-            ))
+            let root_paths = self.root_paths();
+            for root_path in &root_paths {
+                if !root_path.exists() {
+                    eprintln!("error: path does not exist: {}", root_path.display());
+                }
+            }
+            let gitignore = self.gitignore;
+            Ok(root_paths
+                .into_iter()
+                .map(move |root| -> Box<dyn Iterator<Item = _> + Send> {
+                    if gitignore {
+                        Box::new(walk_unignored_files(root))
+                    } else {
+                        Box::new(walk_files(root))
+                    }
+                })
+                .fold(
+                    Box::new(std::iter::empty()) as Box<dyn Iterator<Item = _> + Send>,
+                    |a, b| Box::new(a.chain(b)),
+                ))
         }
     }
+}
 
-    fn matches_owners_filters(&self, file_owners: Option<&[Owner]>) -> bool {
-        if let Some(file_owners) = file_owners {
-            // Owned files. If Some, `file_owners` slice will be non-empty.
-            if self.owners.is_empty() && !self.unowned {
-                // No filters applied
-                return true;
-            }
+impl Cli {
+    const DEFAULT_PATHS: &'static [&'static str] = &["./CODEOWNERS", ".github/CODEOWNERS"];
 
-            for owner in file_owners {
-                if self.owners.contains(&owner.value) {
-                    return true;
-                }
-            }
+    fn codeowners_path(&self) -> Option<PathBuf> {
+        match &self.codeowners_file {
+            Some(path) => Some(path.clone()),
+            None => Self::DEFAULT_PATHS
+                .iter()
+                .map(PathBuf::from)
+                .find(|p| p.exists()),
+        }
+    }
 
-            // No filters matched
-            false
-        } else {
-            // Unowned files
-            // Show unowned files a) if explicitly requested, or b) if no filters were
-            // applied (i.e. self.owners is empty _and_ self.unowned is false).
-            self.unowned || self.owners.is_empty()
+    fn parse_options(&self) -> ParseOptions {
+        let owner_policy = self.owner_policy.unwrap_or(match self.dialect {
+            DialectArg::Github => OwnerPolicyArg::Github,
+            DialectArg::Gitlab => OwnerPolicyArg::Gitlab,
+        });
+        ParseOptions {
+            dialect: self.dialect.into(),
+            owner_policy: owner_policy.into(),
         }
     }
 }
 
-fn main() -> Result<()> {
+// Decides which files to output based on the -o and -u flags. The rules listing
+// the -o owners are looked up in the rule set's owner index up front, so
+// checking a file only needs its winning rule.
+struct OwnersFilter {
+    // The indices of rules listing any of the -o owners, or None if -o wasn't
+    // given.
+    owner_rules: Option<HashSet<usize>>,
+    unowned: bool,
+}
+
+impl OwnersFilter {
+    fn new(cli: &Cli, ruleset: &RuleSet) -> Self {
+        // The index compares owners case-insensitively, but -o matches them
+        // exactly
+        let owner_rules = (!cli.owners.is_empty()).then(|| {
+            cli.owners
+                .iter()
+                .flat_map(|owner| {
+                    ruleset
+                        .rules_owned_by(owner)
+                        .into_iter()
+                        .filter(move |(_, rule)| rule.owners.iter().any(|o| &o.value == owner))
+                })
+                .map(|(idx, _)| idx)
+                .collect()
+        });
+        Self {
+            owner_rules,
+            unowned: cli.unowned,
+        }
+    }
+
+    fn matches(&self, matching_rule: Option<(usize, &Rule)>) -> bool {
+        match matching_rule {
+            Some((idx, rule)) if !rule.owners.is_empty() => match &self.owner_rules {
+                Some(owner_rules) => owner_rules.contains(&idx),
+                // Owned files are shown unless only unowned files were requested
+                None => !self.unowned,
+            },
+            // Show unowned files a) if explicitly requested, or b) if no filters
+            // were applied.
+            _ => self.unowned || self.owner_rules.is_none(),
+        }
+    }
+}
+
+fn main() -> Result<ExitCode> {
     let cli = Cli::parse();
 
     #[cfg(feature = "rayon")]
@@ -117,6 +299,25 @@ fn main() -> Result<()> {
         .num_threads(cli.threads)
         .build_global()?;
 
+    // Subcommands that check something return an exit code rather than exiting
+    // themselves, so that output is flushed before the process exits.
+    let result = match &cli.command {
+        Some(Command::Fmt(args)) => return fmt::run(&cli, args),
+        Some(Command::Lint) => return lint::run(&cli),
+        Some(Command::Check(args)) => return check::run(&cli, args),
+        Some(Command::Diff(args)) => diff::run(&cli, args),
+        Some(Command::Reviewers(args)) => reviewers::run(&cli, args),
+        Some(Command::OwnedBy(args)) => owned_by::run(&cli, args),
+        Some(Command::Stats(args)) => stats::run(&cli, args),
+        Some(Command::Explain(args)) => explain::run(&cli, args),
+        None => match_paths(&cli),
+    };
+    result.map(|()| ExitCode::SUCCESS)
+}
+
+// Find and read the CODEOWNERS file, returning its path and contents. Exits if
+// no CODEOWNERS file could be found.
+fn read_codeowners(cli: &Cli) -> Result<(PathBuf, String)> {
     let Some(codeowners_path) = cli.codeowners_path() else {
         eprintln!("error: no CODEOWNERS file found");
         std::process::exit(1);
@@ -127,27 +328,38 @@ fn main() -> Result<()> {
     let mut source = String::new();
     file.read_to_string(&mut source)
         .with_context(|| format!("reading {:?}", codeowners_path))?;
+    Ok((codeowners_path, source))
+}
 
-    let parse_result = codeowners_rs::parse(&source);
+// Parse the CODEOWNERS file, printing any errors and exiting if there were any.
+fn parse_codeowners(cli: &Cli, path: &Path, source: &str) -> ParseResult {
+    let parse_result = codeowners_rs::parse_with_options(source, &cli.parse_options());
     if !parse_result.errors.is_empty() {
+        let line_index = LineIndex::new(source);
         for (i, error) in parse_result.errors.iter().enumerate() {
-            print_parse_error(&codeowners_path, &source, error);
+            print_parse_error(path, source, &line_index, error);
             if i < parse_result.errors.len() - 1 {
                 println!();
             }
         }
         std::process::exit(1);
     }
-    let ruleset = parse_result.into_ruleset();
+    parse_result
+}
 
-    for root_path in cli.root_paths() {
-        if !root_path.exists() {
-            eprintln!("error: path does not exist: {}", root_path.display());
-            continue;
-        }
-    }
+fn match_paths(cli: &Cli) -> Result<()> {
+    let (codeowners_path, source) = read_codeowners(cli)?;
+    let parse_result = parse_codeowners(cli, &codeowners_path, &source);
+    let line_index = LineIndex::new(&source);
+    let rule_locations = parse_result
+        .rules
+        .iter()
+        .map(|rule| RuleLocation::new(rule.span(), &line_index))
+        .collect::<Vec<_>>();
+    let ruleset = parse_result.into_ruleset();
+    let owners_filter = OwnersFilter::new(cli, &ruleset);
 
-    let paths = cli.paths_iter()?;
+    let paths = cli.path_args.paths_iter()?;
     #[cfg(feature = "rayon")]
     let paths = paths.par_bridge();
 
@@ -156,22 +368,24 @@ fn main() -> Result<()> {
     // Spawn a thread to print output. Doing all stdout writes from a single thread
     // means less time spent locking and unlocking stdout.
     let (output_tx, output_rx) = std::sync::mpsc::sync_channel::<Option<String>>(10000);
+    let (format, row_per_owner) = (cli.format, cli.row_per_owner);
     let join_handle = std::thread::spawn(move || {
         let mut lock = std::io::stdout().lock();
-        for line in output_rx.iter().flatten() {
-            if let Err(err) = lock.write_all(line.as_bytes()) {
-                if err.kind() == std::io::ErrorKind::BrokenPipe {
-                    break;
-                }
-            }
-        }
+        let records = output_rx.iter().flatten();
+        let _ = output::write_records(&mut lock, format, row_per_owner, records);
     });
 
     // Match each of the paths. May happen in parallel if rayon is enabled.
     let _ = paths.try_for_each(|path| {
         let thread_local_ruleset = tl.get_or(|| ruleset.clone());
         let path = path.strip_prefix(".").unwrap_or(&path);
-        output_tx.send(output_for_path(&cli, path, thread_local_ruleset))
+        output_tx.send(output_for_path(
+            cli,
+            path,
+            thread_local_ruleset,
+            &owners_filter,
+            &rule_locations,
+        ))
     });
     drop(output_tx);
 
@@ -181,48 +395,64 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn output_for_path(cli: &Cli, path: impl AsRef<Path>, ruleset: &RuleSet) -> Option<String> {
+fn output_for_path(
+    cli: &Cli,
+    path: impl AsRef<Path>,
+    ruleset: &RuleSet,
+    owners_filter: &OwnersFilter,
+    rule_locations: &[RuleLocation],
+) -> Option<String> {
     let path = path
         .as_ref()
         .strip_prefix(".")
         .unwrap_or_else(|_| path.as_ref());
 
-    #[cfg(debug_assertions)]
-    if cli.all_matching_rules {
-        let matches = ruleset.all_matching_rules(Path::new(path.to_str().unwrap()));
-        for (i, rule) in &matches {
-            eprintln!(
-                "{} matched rule #{}: {}  {}",
-                path.display(),
-                i + 1,
-                rule.pattern,
-                rule.owners
-                    .iter()
-                    .map(|o| o.value.as_str())
-                    .collect::<Vec<&str>>()
-                    .join(" ")
-            );
-        }
-    }
-
-    let owners = ruleset.owners(path);
-    if !cli.matches_owners_filters(owners) {
+    let matching_rule = ruleset
+        .all_matching_rules(path)
+        .into_iter()
+        .max_by_key(|&(idx, _)| idx);
+    if !owners_filter.matches(matching_rule) {
         return None;
     }
-    Some(match owners {
-        Some(owners) => {
-            format!(
-                "{:<70}  {}\n",
-                path.display(),
-                owners
-                    .iter()
-                    .map(|o| o.value.as_str())
-                    .collect::<Vec<&str>>()
-                    .join(" ")
-            )
+    let owners = matching_rule
+        .map(|(_, rule)| rule.owners.as_slice())
+        .filter(|owners| !owners.is_empty());
+    Some(match cli.format {
+        OutputFormat::Text => output::text_record(path, owners),
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            output::delimited_record(cli.format, cli.row_per_owner, path, owners)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let rule = matching_rule.map(|(idx, rule)| (rule, &rule_locations[idx]));
+            output::json_record(path, rule).to_string()
+        }
+    })
+}
+
+// Read paths from a file, one per line. Lines that aren't valid UTF-8 are
+// reported and skipped. A read error is reported and ends the list, as the rest
+// of the file can't be read.
+fn read_paths(reader: impl BufRead, source: PathBuf) -> impl Iterator<Item = PathBuf> {
+    let mut lines = reader.split(b'\n').enumerate();
+    std::iter::from_fn(move || loop {
+        let (idx, line) = lines.next()?;
+        let mut line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("error: reading {:?}: {}", source, e);
+                return None;
+            }
+        };
+        if line.last() == Some(&b'\r') {
+            line.pop();
         }
-        None => {
-            format!("{:<70}  (unowned)\n", path.display())
+        match String::from_utf8(line) {
+            Ok(line) => return Some(PathBuf::from(line)),
+            Err(_) => eprintln!(
+                "error: {} line {}: path is not valid UTF-8",
+                source.display(),
+                idx + 1
+            ),
         }
     })
 }
@@ -236,26 +466,51 @@ fn walk_files(root: impl AsRef<Path>) -> impl Iterator<Item = PathBuf> {
         .map(|entry| entry.into_path())
 }
 
-fn print_parse_error(path: &Path, source: &str, error: &codeowners_rs::parser::ParseError) {
-    let mut line = 1;
-    let mut line_start = 0;
-    let mut line_end = 0;
-    for l in source.lines() {
-        line_end += l.len() + 1;
-        if line_end > error.span.0 {
-            break;
-        }
-        line_start = line_end;
-        line += 1;
-    }
-
-    eprintln!("{} line {}: {}", path.display(), line, error.message);
+// Like `walk_files`, but skips files that git would ignore.
+fn walk_unignored_files(root: impl AsRef<Path>) -> impl Iterator<Item = PathBuf> {
+    ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .ignore(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| !t.is_dir()))
+        .map(|entry| entry.into_path())
+}
 
-    let line_prefix = format!("{:4} | ", line);
-    let context = &source[line_start..line_end];
-    eprint!("{}{}", line_prefix, context);
+fn print_parse_error(path: &Path, source: &str, line_index: &LineIndex, error: &ParseError) {
+    print_diagnostic(path, source, line_index, &error.span, &error.message);
+}
 
-    let padding = " ".repeat(error.span.0 - line_start + line_prefix.len());
-    let underline = "^".repeat(error.span.1 - error.span.0);
+// Print a message about a span of the CODEOWNERS file, along with the line
+// containing the span, underlining the span itself.
+fn print_diagnostic(path: &Path, source: &str, line_index: &LineIndex, span: &Span, message: &str) {
+    let start = span.start_position(line_index);
+    eprintln!("{} line {}: {}", path.display(), start.line, message);
+
+    let line_prefix = format!("{:4} | ", start.line);
+    let line_span = line_index
+        .line_span(start.line)
+        .expect("spans are within the source");
+    let context = source[line_span.0..line_span.1].trim_end_matches(['\r', '\n']);
+    eprintln!("{}{}", line_prefix, context);
+
+    // Only underline the first line of multi-line spans
+    let end = span.1.min(line_span.0 + context.len());
+    // Pad by characters rather than bytes, so the underline lines up on lines
+    // with non-ASCII text before the span
+    let column = source[line_span.0..span.0].chars().count();
+    let padding = " ".repeat(column + line_prefix.len());
+    let width = source.get(span.0..end).map_or(0, |s| s.chars().count());
+    let underline = "^".repeat(width.max(1));
     eprintln!("{}{}", padding, underline);
 }
+
+// Choose the singular or plural form of a word to go with a count.
+fn plural<'a>(count: usize, singular: &'a str, plural: &'a str) -> &'a str {
+    if count == 1 {
+        singular
+    } else {
+        plural
+    }
+}
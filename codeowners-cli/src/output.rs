@@ -0,0 +1,174 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+use codeowners_rs::{
+    parser::{LineIndex, Span},
+    Owner, OwnerKind, Rule,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// One line per file, with the file's owners aligned in a column
+    Text,
+    /// A single JSON array with an object per file
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Comma-separated values, with a header row
+    Csv,
+    /// Tab-separated values, with a header row
+    Tsv,
+}
+
+impl OutputFormat {
+    fn delimiter(self) -> Option<u8> {
+        match self {
+            OutputFormat::Csv => Some(b','),
+            OutputFormat::Tsv => Some(b'\t'),
+            _ => None,
+        }
+    }
+}
+
+// Where a rule lives in the CODEOWNERS file.
+pub(crate) struct RuleLocation {
+    // 1-based line number
+    line: usize,
+    span: Span,
+}
+
+impl RuleLocation {
+    pub(crate) fn new(span: Span, line_index: &LineIndex) -> Self {
+        Self {
+            line: span.start_position(line_index).line,
+            span,
+        }
+    }
+}
+
+pub(crate) fn text_record(path: &Path, owners: Option<&[Owner]>) -> String {
+    let owners = match owners {
+        Some(owners) => owners
+            .iter()
+            .map(|o| o.value.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        None => "(unowned)".to_string(),
+    };
+    format!("{:<70}  {}\n", path.display(), owners)
+}
+
+// Rows for a single path in csv or tsv output. With `row_per_owner`, there's a
+// row for each owner (or a single row with an empty owner for unowned files);
+// otherwise there's one row with the owners separated by spaces.
+pub(crate) fn delimited_record(
+    format: OutputFormat,
+    row_per_owner: bool,
+    path: &Path,
+    owners: Option<&[Owner]>,
+) -> String {
+    let path = path.to_string_lossy();
+    let owners = owners.unwrap_or_default();
+    if row_per_owner && !owners.is_empty() {
+        let rows = owners.iter().map(|owner| [&*path, owner.value.as_str()]);
+        write_delimited(format, rows)
+    } else {
+        let joined = owners
+            .iter()
+            .map(|o| o.value.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        write_delimited(format, [[&*path, joined.as_str()]])
+    }
+}
+
+fn delimited_header(format: OutputFormat, row_per_owner: bool) -> String {
+    let owner_column = if row_per_owner { "owner" } else { "owners" };
+    write_delimited(format, [["path", owner_column]])
+}
+
+fn write_delimited<'a>(
+    format: OutputFormat,
+    rows: impl IntoIterator<Item = [&'a str; 2]>,
+) -> String {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(format.delimiter().expect("format is csv or tsv"))
+        .from_writer(Vec::new());
+    for row in rows {
+        writer
+            .write_record(row)
+            .expect("writing to a Vec can't fail");
+    }
+    let bytes = writer.into_inner().expect("writing to a Vec can't fail");
+    String::from_utf8(bytes).expect("records are valid UTF-8")
+}
+
+pub(crate) fn json_record(path: &Path, rule: Option<(&Rule, &RuleLocation)>) -> Value {
+    let owners = rule.map_or(&[][..], |(rule, _)| rule.owners.as_slice());
+    json!({
+        "path": path.to_string_lossy(),
+        "owners": owners.iter().map(owner_json).collect::<Vec<_>>(),
+        "rule": rule.map(|(rule, location)| json!({
+            "pattern": rule.pattern,
+            "line": location.line,
+            "span": [location.span.0, location.span.1],
+        })),
+    })
+}
+
+pub(crate) fn owner_json(owner: &Owner) -> Value {
+    json!({
+        "value": owner.value,
+        "kind": owner_kind_name(&owner.kind),
+    })
+}
+
+pub(crate) fn owner_kind_name(kind: &OwnerKind) -> &'static str {
+    match kind {
+        OwnerKind::User => "user",
+        OwnerKind::Team => "team",
+        OwnerKind::Email => "email",
+    }
+}
+
+// Write records produced by `text_record`, `delimited_record` or `json_record`
+// in the given format. JSON records are wrapped in an array, and csv and tsv
+// records are preceded by a header row. Stops early if the reader has gone
+// away.
+pub(crate) fn write_records(
+    writer: &mut impl Write,
+    format: OutputFormat,
+    row_per_owner: bool,
+    records: impl Iterator<Item = String>,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Text | OutputFormat::Csv | OutputFormat::Tsv => {
+            if format.delimiter().is_some() {
+                writer.write_all(delimited_header(format, row_per_owner).as_bytes())?;
+            }
+            for record in records {
+                writer.write_all(record.as_bytes())?;
+            }
+        }
+        OutputFormat::Ndjson => {
+            for record in records {
+                writer.write_all(record.as_bytes())?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        OutputFormat::Json => {
+            writer.write_all(b"[")?;
+            for (i, record) in records.enumerate() {
+                writer.write_all(if i == 0 { b"\n  " } else { b",\n  " })?;
+                writer.write_all(record.as_bytes())?;
+            }
+            writer.write_all(b"\n]\n")?;
+        }
+    }
+    writer.flush()
+}
@@ -0,0 +1,57 @@
+use std::{io::Write, path::PathBuf};
+
+use anyhow::Result;
+use clap::Args;
+use codeowners_rs::parser::LineIndex;
+
+use crate::{parse_codeowners, read_codeowners, Cli, PathArgs};
+
+#[derive(Args)]
+pub(crate) struct OwnedByArgs {
+    /// The owner to look up, such as @org/team
+    owner: String,
+
+    /// List the rules that mention the owner rather than the files it owns
+    #[arg(long)]
+    rules: bool,
+
+    #[command(flatten)]
+    path_args: PathArgs,
+}
+
+pub(crate) fn run(cli: &Cli, args: &OwnedByArgs) -> Result<()> {
+    let (codeowners_path, source) = read_codeowners(cli)?;
+    let parse_result = parse_codeowners(cli, &codeowners_path, &source);
+    let rule_spans = parse_result
+        .rules
+        .iter()
+        .map(|rule| rule.span())
+        .collect::<Vec<_>>();
+    let ruleset = parse_result.into_ruleset();
+    let mut stdout = std::io::stdout().lock();
+
+    if args.rules {
+        let line_index = LineIndex::new(&source);
+        for (idx, _) in ruleset.rules_owned_by(&args.owner) {
+            let span = &rule_spans[idx];
+            writeln!(
+                stdout,
+                "{}:{}: {}",
+                codeowners_path.display(),
+                span.start_position(&line_index).line,
+                &source[span.0..span.1]
+            )?;
+        }
+        return Ok(());
+    }
+
+    let paths = args
+        .path_args
+        .paths_iter()?
+        .map(|path| path.strip_prefix(".").map(PathBuf::from).unwrap_or(path))
+        .collect::<Vec<_>>();
+    for idx in ruleset.paths_owned_by(&args.owner, &paths) {
+        writeln!(stdout, "{}", paths[idx].display())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use std::{io::Write, path::PathBuf};
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::{parse_codeowners, plural, read_codeowners, Cli, PathArgs};
+
+#[derive(Args)]
+pub(crate) struct ReviewersArgs {
+    #[command(flatten)]
+    path_args: PathArgs,
+}
+
+pub(crate) fn run(cli: &Cli, args: &ReviewersArgs) -> Result<()> {
+    let (codeowners_path, source) = read_codeowners(cli)?;
+    let ruleset = parse_codeowners(cli, &codeowners_path, &source).into_ruleset();
+
+    let paths = args
+        .path_args
+        .paths_iter()?
+        .map(|path| path.strip_prefix(".").map(PathBuf::from).unwrap_or(path))
+        .collect::<Vec<_>>();
+    let suggestion = ruleset.suggest_reviewers(&paths);
+
+    let mut stdout = std::io::stdout().lock();
+    for reviewer in &suggestion.reviewers {
+        writeln!(stdout, "{}", reviewer.value)?;
+    }
+    let unowned = suggestion.unowned.len();
+    if unowned > 0 {
+        eprintln!(
+            "{} {} with no owners:",
+            unowned,
+            plural(unowned, "file", "files")
+        );
+        for &idx in &suggestion.unowned {
+            eprintln!("  {}", paths[idx].display());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,262 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    io::Write,
+    path::Path,
+};
+
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde_json::{json, Value};
+
+use codeowners_rs::Owner;
+
+use crate::{output, parse_codeowners, plural, read_codeowners, Cli, PathArgs};
+
+#[derive(Args)]
+pub(crate) struct StatsArgs {
+    #[command(flatten)]
+    path_args: PathArgs,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = StatsFormat::Text)]
+    format: StatsFormat,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum StatsFormat {
+    Text,
+    Json,
+}
+
+// Counts of matched files. Files are counted once for each owner of their
+// winning rule, and once for each distinct kind of owner. Owners are compared
+// case-insensitively, as they are by the ruleset, so they're keyed in lower
+// case and displayed as written in the CODEOWNERS file.
+#[derive(Default)]
+struct Stats {
+    totals: Counts,
+    owners: HashMap<String, usize>,
+    // The display name for each lowercased owner. If an owner is written in
+    // more than one way, the first in sort order is used.
+    owner_names: HashMap<String, String>,
+    owner_kinds: HashMap<&'static str, usize>,
+    // Keyed by the first component of each path, or "." for files at the root
+    directories: HashMap<String, Counts>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Counts {
+    files: usize,
+    unowned: usize,
+}
+
+impl Counts {
+    fn add(&mut self, owned: bool) {
+        self.files += 1;
+        if !owned {
+            self.unowned += 1;
+        }
+    }
+
+    fn merge(&mut self, other: Counts) {
+        self.files += other.files;
+        self.unowned += other.unowned;
+    }
+
+    fn owned(&self) -> usize {
+        self.files - self.unowned
+    }
+
+    // The percentage of files that are owned, or None if there are no files.
+    fn coverage(&self) -> Option<f64> {
+        (self.files > 0).then(|| self.owned() as f64 * 100.0 / self.files as f64)
+    }
+
+    fn to_json(self) -> Value {
+        json!({
+            "files": self.files,
+            "owned": self.owned(),
+            "unowned": self.unowned,
+            "coverage": self.coverage(),
+        })
+    }
+}
+
+impl Stats {
+    fn add(&mut self, path: &Path, owners: Option<&[Owner]>) {
+        let owners = owners.unwrap_or_default();
+        self.totals.add(!owners.is_empty());
+
+        let mut owners = owners
+            .iter()
+            .map(|o| (o.value.to_lowercase(), o))
+            .collect::<Vec<_>>();
+        owners.sort_by(|a, b| a.0.cmp(&b.0));
+        owners.dedup_by(|a, b| a.0 == b.0);
+        for (owner, o) in &owners {
+            *self.owners.entry(owner.clone()).or_default() += 1;
+            self.add_owner_name(owner, &o.value);
+        }
+        let mut kinds = owners
+            .iter()
+            .map(|(_, o)| output::owner_kind_name(&o.kind))
+            .collect::<Vec<_>>();
+        kinds.sort();
+        kinds.dedup();
+        for kind in kinds {
+            *self.owner_kinds.entry(kind).or_default() += 1;
+        }
+
+        let mut components = path.components();
+        let directory = match (components.next(), components.next()) {
+            (Some(first), Some(_)) => first.as_os_str().to_string_lossy().into_owned(),
+            _ => ".".to_string(),
+        };
+        self.directories
+            .entry(directory)
+            .or_default()
+            .add(!owners.is_empty());
+    }
+
+    fn merge(&mut self, other: Stats) {
+        self.totals.merge(other.totals);
+        for (owner, count) in other.owners {
+            *self.owners.entry(owner).or_default() += count;
+        }
+        for (owner, name) in &other.owner_names {
+            self.add_owner_name(owner, name);
+        }
+        for (kind, count) in other.owner_kinds {
+            *self.owner_kinds.entry(kind).or_default() += count;
+        }
+        for (directory, counts) in other.directories {
+            self.directories.entry(directory).or_default().merge(counts);
+        }
+    }
+
+    fn add_owner_name(&mut self, owner: &str, name: &str) {
+        match self.owner_names.get_mut(owner) {
+            Some(existing) if name < existing.as_str() => *existing = name.to_string(),
+            Some(_) => {}
+            None => {
+                self.owner_names.insert(owner.to_string(), name.to_string());
+            }
+        }
+    }
+
+    // Owner counts keyed by the owners' display names.
+    fn owner_counts(&self) -> HashMap<&str, usize> {
+        self.owners
+            .iter()
+            .map(|(owner, &count)| (self.owner_names[owner].as_str(), count))
+            .collect()
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "total": self.totals.to_json(),
+            "owners": sorted(&self.owner_counts()),
+            "owner_kinds": sorted(&self.owner_kinds),
+            "directories": sorted(&self.directories)
+                .into_iter()
+                .map(|(directory, counts)| (directory, counts.to_json()))
+                .collect::<BTreeMap<_, _>>(),
+        })
+    }
+
+    fn write_text(&self, w: &mut impl Write) -> Result<()> {
+        writeln!(w, "Files:    {}", self.totals.files)?;
+        writeln!(w, "Owned:    {}", self.totals.owned())?;
+        writeln!(w, "Unowned:  {}", self.totals.unowned)?;
+        writeln!(w, "Coverage: {}", format_coverage(&self.totals))?;
+
+        writeln!(w, "\nOwners:")?;
+        write_counts(w, &self.owner_counts())?;
+        writeln!(w, "\nOwner kinds:")?;
+        write_counts(w, &self.owner_kinds)?;
+
+        writeln!(w, "\nDirectories:")?;
+        let directories = sorted(&self.directories);
+        let width = directories.keys().map(|d| d.len()).max().unwrap_or(0);
+        for (directory, counts) in directories {
+            writeln!(
+                w,
+                "  {:<width$}  {:>6} {:<5}  {:>6} unowned  {:>6}",
+                directory,
+                counts.files,
+                plural(counts.files, "file", "files"),
+                counts.unowned,
+                format_coverage(counts),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn sorted<K: Ord, V>(map: &HashMap<K, V>) -> BTreeMap<&K, &V> {
+    map.iter().collect()
+}
+
+fn write_counts<K: Ord + std::fmt::Display>(
+    w: &mut impl Write,
+    counts: &HashMap<K, usize>,
+) -> Result<()> {
+    let width = counts
+        .keys()
+        .map(|k| k.to_string().len())
+        .max()
+        .unwrap_or(0);
+    for (key, count) in sorted(counts) {
+        writeln!(
+            w,
+            "  {:<width$}  {:>6} {}",
+            key.to_string(),
+            count,
+            plural(*count, "file", "files")
+        )?;
+    }
+    Ok(())
+}
+
+fn format_coverage(counts: &Counts) -> String {
+    match counts.coverage() {
+        Some(coverage) => format!("{:.1}%", coverage),
+        None => "-".to_string(),
+    }
+}
+
+pub(crate) fn run(cli: &Cli, args: &StatsArgs) -> Result<()> {
+    let (codeowners_path, source) = read_codeowners(cli)?;
+    let ruleset = parse_codeowners(cli, &codeowners_path, &source).into_ruleset();
+
+    let paths = args.path_args.paths_iter()?;
+    #[cfg(feature = "rayon")]
+    let paths = paths.par_bridge();
+
+    // Each thread counts the paths it matches, and the counts are merged at
+    // the end.
+    let tl = thread_local::ThreadLocal::new();
+    let tl_stats = thread_local::ThreadLocal::<RefCell<Stats>>::new();
+    paths.for_each(|path| {
+        let ruleset = tl.get_or(|| ruleset.clone());
+        let path = path.strip_prefix(".").unwrap_or(&path);
+        tl_stats
+            .get_or_default()
+            .borrow_mut()
+            .add(path, ruleset.owners(path));
+    });
+    let mut stats = Stats::default();
+    for thread_stats in tl_stats {
+        stats.merge(thread_stats.into_inner());
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    match args.format {
+        StatsFormat::Text => stats.write_text(&mut stdout)?,
+        StatsFormat::Json => writeln!(stdout, "{}", stats.to_json())?,
+    }
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use std::thread;
+
 use codeowners_rs::{Rule, RuleSet};
 use criterion::{criterion_group, criterion_main, Criterion};
 
@@ -46,5 +48,197 @@ fn patternset_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, patternset_benchmark);
+// A few thousand paths spread over a directory tree, so matching threads look
+// up many different prefixes in the transition cache.
+fn synthetic_paths() -> Vec<String> {
+    let mut paths = Vec::new();
+    for a in 0..10 {
+        for b in 0..10 {
+            for c in 0..30 {
+                paths.push(format!("dir-{}/sub-{}/file-{}.rs", a, b, c));
+            }
+        }
+    }
+    paths
+}
+
+// Clone the rule set for each thread, as the CLI does. With `share_cache`,
+// the clones share a transition cache; otherwise each gets a cache of its own.
+fn clone_per_thread(ruleset: &RuleSet, threads: usize, share_cache: bool) -> Vec<RuleSet> {
+    (0..threads)
+        .map(|_| {
+            let mut ruleset = ruleset.clone();
+            if !share_cache {
+                ruleset.set_cache_capacity(ruleset.cache_stats().capacity);
+            }
+            ruleset
+        })
+        .collect()
+}
+
+// Match every path, splitting the paths evenly between the rule sets, each of
+// which is used by its own thread.
+fn match_in_parallel(rulesets: &[RuleSet], paths: &[String]) {
+    let chunk_size = paths.len().div_ceil(rulesets.len());
+    thread::scope(|s| {
+        for (ruleset, chunk) in rulesets.iter().zip(paths.chunks(chunk_size)) {
+            s.spawn(move || {
+                for path in chunk {
+                    ruleset.matching_rule(path);
+                }
+            });
+        }
+    });
+}
+
+fn parallel_matching_benchmark(c: &mut Criterion) {
+    let threads = thread::available_parallelism().map_or(4, |n| n.get());
+    let paths = synthetic_paths();
+    let patternset = build_patternset(TEST_PATTERNS);
+
+    let mut group = c.benchmark_group(format!("parallel matching ({} threads)", threads));
+    for (name, threads, share_cache) in [
+        ("shared cache", threads, true),
+        ("per-thread caches", threads, false),
+        ("single thread", 1, true),
+    ] {
+        let rulesets = clone_per_thread(&patternset, threads, share_cache);
+        group.bench_function(name, |b| b.iter(|| match_in_parallel(&rulesets, &paths)));
+    }
+    group.finish();
+}
+
+// A rule set in the style of a large monorepo's CODEOWNERS file: thousands of
+// directory rules, plus a sprinkling of extension and wildcard rules. Each
+// unanchored directory rule (like `**/generated/`) multiplies the size of the
+// DFA, so there are only a few.
+fn synthetic_patterns() -> Vec<String> {
+    let mut patterns = vec!["*".to_string()];
+    for ext in [
+        "rs", "go", "rb", "py", "js", "ts", "md", "yml", "json", "proto",
+    ] {
+        patterns.push(format!("*.{}", ext));
+    }
+    for a in 0..50 {
+        patterns.push(format!("/services/service-{}/", a));
+        for b in 0..20 {
+            patterns.push(format!("/services/service-{}/pkg-{}/", a, b));
+            patterns.push(format!("/libs/lib-{}/pkg-{}/**/*.go", a, b));
+        }
+        if a < 5 {
+            patterns.push(format!("**/generated-{}/", a));
+        }
+        patterns.push(format!("/services/service-{}/*_test.go", a));
+    }
+    patterns.push("/docs/**/*.md".to_string());
+    patterns.push("test-*/".to_string());
+    patterns
+}
+
+fn synthetic_monorepo_paths() -> Vec<String> {
+    let mut paths = Vec::new();
+    for a in 0..60 {
+        for b in 0..25 {
+            paths.push(format!("services/service-{}/pkg-{}/handler.go", a, b));
+            paths.push(format!("services/service-{}/pkg-{}/handler_test.go", a, b));
+            paths.push(format!("libs/lib-{}/pkg-{}/internal/util.go", a, b));
+            paths.push(format!("libs/lib-{}/pkg-{}/generated-{}/api.rs", a, b, b));
+        }
+    }
+    paths
+}
+
+fn large_ruleset_benchmark(c: &mut Criterion) {
+    let patterns = synthetic_patterns();
+    let patterns = patterns.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+    let paths = synthetic_monorepo_paths();
+
+    let mut group = c.benchmark_group(format!("large ruleset ({} rules)", patterns.len()));
+    group.sample_size(10);
+    group.bench_function("determinizing", |b| {
+        b.iter_batched(
+            || build_patternset(&patterns),
+            |mut ruleset| assert!(ruleset.determinize(100_000)),
+            criterion::BatchSize::SmallInput,
+        )
+    });
+
+    // The NFA's transition cache helps most when paths share directories, as
+    // they do here, so the NFA is also measured without it
+    let nfa = build_patternset(&patterns);
+    let mut uncached_nfa = nfa.clone();
+    uncached_nfa.set_cache_capacity(0);
+    let mut dfa = nfa.clone();
+    assert!(dfa.determinize(100_000));
+    for (name, ruleset) in [
+        ("matching (nfa)", &nfa),
+        ("matching (nfa, no cache)", &uncached_nfa),
+        ("matching (dfa)", &dfa),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for path in &paths {
+                    ruleset.matching_rule(path);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+// Thousands of top-level directories, each with its own rule, so the root
+// state has thousands of literal transitions. Caching is disabled so every
+// path steps through the root.
+fn wide_fan_out_benchmark(c: &mut Criterion) {
+    let patterns = (0..5000)
+        .map(|i| format!("/team-{}/", i))
+        .chain((0..500).map(|i| format!("*.ext{}", i)))
+        .collect::<Vec<_>>();
+    let patterns = patterns.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+    let paths = (0..5000)
+        .step_by(7)
+        .map(|i| format!("team-{}/src/file.ext{}", i, i % 600))
+        .collect::<Vec<_>>();
+
+    let mut ruleset = build_patternset(&patterns);
+    ruleset.set_cache_capacity(0);
+    c.bench_function("matching (wide fan-out)", |b| {
+        b.iter(|| {
+            for path in &paths {
+                ruleset.matching_rule(path);
+            }
+        })
+    });
+}
+
+// Hundreds of patterns whose segments need more than a prefix or suffix
+// comparison, all leaving the same state.
+fn wildcard_heavy_benchmark(c: &mut Criterion) {
+    let patterns = (0..200)
+        .flat_map(|i| [format!("*gen{}*", i), format!("v{}-*.go", i)])
+        .collect::<Vec<_>>();
+    let patterns = patterns.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+    let paths = (0..300)
+        .map(|i| format!("src/v{}-api.go", i))
+        .collect::<Vec<_>>();
+
+    let mut ruleset = build_patternset(&patterns);
+    ruleset.set_cache_capacity(0);
+    c.bench_function("matching (wildcard heavy)", |b| {
+        b.iter(|| {
+            for path in &paths {
+                ruleset.matching_rule(path);
+            }
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    patternset_benchmark,
+    parallel_matching_benchmark,
+    large_ruleset_benchmark,
+    wide_fan_out_benchmark,
+    wildcard_heavy_benchmark
+);
 criterion_main!(benches);
@@ -14,6 +14,10 @@
 //! syntax, for instance when writing a syntax highlighter, but it's not very
 //! ergonomic for most use cases.
 //!
+//! A [`parser::ParseResult`] can be turned back into CODEOWNERS source with
+//! the [`print`] function, which preserves comments, blank lines and the
+//! original formatting of anything that hasn't been edited.
+//!
 //! The [`RuleSet`] struct provides a more ergonomic interface for working with
 //! CODEOWNERS files. It can be constructed by calling
 //! [`into_ruleset`](fn@parser::ParseResult::into_ruleset) on a
@@ -41,6 +45,37 @@
 //! }
 //! ```
 //!
+//! ## GitLab sections
+//! GitLab extends the CODEOWNERS syntax with section headers such as
+//! `[Documentation]`, `^[Optional Section]` and `[Section][2] @default-owner`.
+//! These are parsed when using [`parse_with_options`] with
+//! [`parser::Dialect::GitLab`]. In a GitLab rule set, every section contributes
+//! its own last-matching rule, which can be retrieved with the
+//! [`matching_rules`](fn@RuleSet::matching_rules) method. To also get each
+//! section's approval requirements, use
+//! [`section_matches`](fn@RuleSet::section_matches). GitLab owners, such as
+//! nested groups like `@org/group/subgroup`, are only accepted when
+//! [`parser::ParseOptions::owner_policy`] is set to [`OwnerPolicy::GitLab`].
+//!
+//! ```
+//! use codeowners_rs::{parse_with_options, parser::{Dialect, ParseOptions}};
+//!
+//! let options = ParseOptions {
+//!     dialect: Dialect::GitLab,
+//!     ..Default::default()
+//! };
+//! let ruleset = parse_with_options("
+//! *.rs @rustaceans
+//! /docs/ @writers
+//!
+//! [Docs] @docs-team
+//! *.md
+//! ", &options).into_ruleset();
+//!
+//! assert_eq!(ruleset.matching_rules("README.md").len(), 1);
+//! assert_eq!(ruleset.matching_rules("docs/README.md").len(), 2);
+//! ```
+//!
 //! ## Command line interface
 //! There is a companion binary crate that provides a simple CLI for matching
 //! paths against a CODEOWNERS file.
@@ -49,5 +84,8 @@ pub mod parser;
 pub mod patternset;
 mod ruleset;
 
-pub use parser::{parse, parse_file};
-pub use ruleset::{Owner, Rule, RuleSet};
+pub use parser::{parse, parse_file, parse_with_options, print};
+pub use ruleset::{
+    InvalidOwnerError, Owner, OwnerKind, OwnerPolicy, OwnerValidator, ReviewerSuggestion, Rule,
+    RuleSet, Section, SectionMatch,
+};
@@ -0,0 +1,278 @@
+use super::{
+    printer::{pattern_source, section_header},
+    LineIndex, ParseResult, Rule, Section, Span, Spanned,
+};
+use crate::ruleset::Owner;
+
+/// Options that control how [`format`] lays out a CODEOWNERS file.
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// Align the owners of consecutive rules into a single column. Blank
+    /// lines, comment lines and section headers start a new block, and each
+    /// block is aligned independently.
+    pub align_owners: bool,
+    /// Sort each rule's owners alphabetically (ignoring case).
+    pub sort_owners: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            align_owners: true,
+            sort_owners: false,
+        }
+    }
+}
+
+/// Format a parsed CODEOWNERS file, returning the formatted source. `original`
+/// should be the source the `ParseResult` was parsed from; it's used to find
+/// the blank lines separating groups of rules.
+///
+/// Formatting removes indentation and trailing whitespace, collapses runs of
+/// blank lines, separates the components of each rule with a single space (or
+/// aligns owners into a column, see [`FormatOptions`]), and removes duplicate
+/// owners within a rule. Rules are never reordered, as their order determines
+/// precedence, and comments stay attached to the rules they precede or
+/// follow. Lines that failed to parse aren't part of the `ParseResult`, so
+/// callers should check for errors before formatting.
+///
+/// # Example
+/// ```
+/// use codeowners_rs::{parse, parser::{format, FormatOptions}};
+///
+/// let source = "  *.rs  @a @b @a\n/docs/ @docs # docs\n";
+/// assert_eq!(
+///     format(&parse(source), source, &FormatOptions::default()),
+///     "*.rs   @a @b\n/docs/ @docs # docs\n"
+/// );
+/// ```
+pub fn format(result: &ParseResult, original: &str, options: &FormatOptions) -> String {
+    // Flatten the parse tree into the lines we'll print, in source order.
+    let mut lines = Vec::new();
+    let mut sections = result.sections.iter().peekable();
+    for (idx, rule) in result.rules.iter().enumerate() {
+        while let Some(section) = sections.next_if(|s| s.first_rule <= idx) {
+            push_section(&mut lines, section);
+        }
+        lines.extend(rule.leading_comments.iter().map(Line::Comment));
+        lines.push(Line::Rule(rule));
+    }
+    for section in sections {
+        push_section(&mut lines, section);
+    }
+    lines.extend(result.trailing_comments.iter().map(Line::Comment));
+
+    // Work out which lines were preceded by a blank line in the original
+    let blank_before = (0..lines.len())
+        .map(|i| i > 0 && has_blank_line_between(original, &lines[i - 1], &lines[i]))
+        .collect::<Vec<_>>();
+
+    let patterns = lines
+        .iter()
+        .map(|line| match line {
+            Line::Rule(rule) => pattern_source(&rule.pattern, original),
+            _ => String::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut output = String::new();
+    let mut block_start = 0;
+    while block_start < lines.len() {
+        // A block is a run of consecutive rules; comments and sections are
+        // blocks of their own.
+        let mut block_end = block_start + 1;
+        if matches!(lines[block_start], Line::Rule(_)) {
+            while block_end < lines.len()
+                && matches!(lines[block_end], Line::Rule(_))
+                && !blank_before[block_end]
+            {
+                block_end += 1;
+            }
+        }
+
+        let width = if options.align_owners {
+            (block_start..block_end)
+                .filter(|&i| matches!(lines[i], Line::Rule(rule) if !rule.owners.is_empty()))
+                .map(|i| patterns[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        for i in block_start..block_end {
+            if blank_before[i] {
+                output.push('\n');
+            }
+            match lines[i] {
+                Line::Comment(comment) => output.push_str(comment.0.trim_end()),
+                Line::Rule(rule) => {
+                    output.push_str(&patterns[i]);
+                    if !rule.owners.is_empty() {
+                        let padding = width.saturating_sub(patterns[i].chars().count());
+                        output.push_str(&" ".repeat(padding));
+                    }
+                    push_owners(&mut output, &rule.owners, options);
+                    push_trailing_comment(&mut output, &rule.trailing_comment);
+                }
+                Line::Section(section) => {
+                    output.push_str(&section_header(section));
+                    push_owners(&mut output, &section.default_owners, options);
+                    push_trailing_comment(&mut output, &section.trailing_comment);
+                }
+            }
+            output.push('\n');
+        }
+        block_start = block_end;
+    }
+    output
+}
+
+enum Line<'a> {
+    Comment(&'a Spanned<String>),
+    Rule(&'a Rule),
+    Section(&'a Section),
+}
+
+impl Line<'_> {
+    fn span(&self) -> Span {
+        match self {
+            Line::Comment(comment) => comment.1.clone(),
+            Line::Rule(rule) => rule.span(),
+            Line::Section(section) => {
+                let end = match (&section.trailing_comment, section.default_owners.last()) {
+                    (Some(comment), _) => comment.1 .1,
+                    (None, Some(owner)) => owner.1 .1,
+                    (None, None) => section.span.1,
+                };
+                Span(section.span.0, end)
+            }
+        }
+    }
+}
+
+fn push_section<'a>(lines: &mut Vec<Line<'a>>, section: &'a Section) {
+    lines.extend(section.leading_comments.iter().map(Line::Comment));
+    lines.push(Line::Section(section));
+}
+
+fn has_blank_line_between(original: &str, prev: &Line, next: &Line) -> bool {
+    let (prev, next) = (prev.span(), next.span());
+    original
+        .get(prev.1..next.0)
+        .map(|between| LineIndex::new(between).line_count() > 2)
+        .unwrap_or(false)
+}
+
+fn push_owners(output: &mut String, owners: &[Spanned<Owner>], options: &FormatOptions) {
+    let mut values: Vec<&str> = Vec::with_capacity(owners.len());
+    for owner in owners {
+        let value = owner.0.value.as_str();
+        if !values.iter().any(|v| v.eq_ignore_ascii_case(value)) {
+            values.push(value);
+        }
+    }
+    if options.sort_owners {
+        values.sort_by_key(|v| v.to_lowercase());
+    }
+    for value in values {
+        output.push(' ');
+        output.push_str(value);
+    }
+}
+
+fn push_trailing_comment(output: &mut String, comment: &Option<Spanned<String>>) {
+    if let Some(comment) = comment {
+        output.push(' ');
+        output.push_str(comment.0.trim_end());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{parse_with_options, Dialect, ParseOptions};
+
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let source = "
+
+# Rust
+  *.rs    @a   @b @A   # rustaceans
+/build/logs/ @c
+docs/
+/docs/** @d\t
+
+
+# Go
+*.go @f @e
+# trailing
+";
+        let expected = "# Rust
+*.rs         @a @b # rustaceans
+/build/logs/ @c
+docs/
+/docs/**     @d
+
+# Go
+*.go @f @e
+# trailing
+";
+        let options = FormatOptions::default();
+        let formatted = format(&crate::parse(source), source, &options);
+        assert_eq!(formatted, expected);
+        assert_eq!(
+            format(&crate::parse(expected), expected, &options),
+            expected
+        );
+
+        let options = FormatOptions {
+            align_owners: false,
+            sort_owners: true,
+        };
+        assert_eq!(
+            format(&crate::parse(source), source, &options),
+            "# Rust
+*.rs @a @b # rustaceans
+/build/logs/ @c
+docs/
+/docs/** @d
+
+# Go
+*.go @e @f
+# trailing
+"
+        );
+
+        assert_eq!(format(&crate::parse(""), "", &options), "");
+    }
+
+    #[test]
+    fn test_format_carriage_returns() {
+        // Lone carriage returns separate lines, so blank lines made of them
+        // still split rules into blocks
+        let options = FormatOptions::default();
+        for newline in ["\r", "\r\n"] {
+            let source = ["*.rs @a", "/build/ @b", "", "# Go", "*.go @c", ""].join(newline);
+            assert_eq!(
+                format(&crate::parse(&source), &source, &options),
+                "*.rs    @a\n/build/ @b\n\n# Go\n*.go @c\n"
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_sections() {
+        let source = "*.md @a\n^[Docs][2]   @b @b #docs \n/docs/ @c\n\n[Go]\n*.go   @d\n";
+        let gitlab = ParseOptions {
+            dialect: Dialect::GitLab,
+            ..Default::default()
+        };
+        let result = parse_with_options(source, &gitlab);
+        assert_eq!(
+            format(&result, source, &FormatOptions::default()),
+            "*.md @a\n^[Docs][2] @b #docs\n/docs/ @c\n\n[Go]\n*.go @d\n"
+        );
+    }
+}
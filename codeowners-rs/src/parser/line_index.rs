@@ -0,0 +1,169 @@
+use super::Span;
+
+/// A 1-based line and column position in a CODEOWNERS file. `column` counts
+/// UTF-8 bytes from the start of the line, whereas `utf16_column` counts UTF-16
+/// code units, which is what LSP clients and many editors expect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub utf16_column: usize,
+}
+
+/// Converts byte offsets in a CODEOWNERS file (such as those in a [`Span`])
+/// into line and column positions. Construct it once per file, after which
+/// each lookup takes O(log n) time.
+///
+/// # Example
+/// ```
+/// use codeowners_rs::parser::{LineIndex, Position};
+///
+/// let index = LineIndex::new("*.rs @a\n/docs/ @b\n");
+/// assert_eq!(
+///     index.position(13),
+///     Position { line: 2, column: 6, utf16_column: 6 }
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    len: usize,
+    // Byte offset of the start of each line.
+    line_starts: Vec<usize>,
+    // For each character that's more than one byte long in UTF-8, its byte
+    // offset and the running total of the difference between its UTF-8 and
+    // UTF-16 lengths, including this character. This lets us convert byte
+    // columns to UTF-16 columns with a binary search.
+    wide_chars: Vec<(usize, usize)>,
+}
+
+impl LineIndex {
+    /// Build a `LineIndex` for the given source. Lines may end with `\n`,
+    /// `\r\n` or a lone `\r`, as they can for the parser.
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut wide_chars = Vec::new();
+        let mut adjustment = 0;
+        let mut chars = source.char_indices().peekable();
+        while let Some((offset, c)) = chars.next() {
+            if c == '\n' || (c == '\r' && !matches!(chars.peek(), Some((_, '\n')))) {
+                line_starts.push(offset + 1);
+            } else if c.len_utf8() > 1 {
+                adjustment += c.len_utf8() - c.len_utf16();
+                wide_chars.push((offset, adjustment));
+            }
+        }
+        Self {
+            len: source.len(),
+            line_starts,
+            wide_chars,
+        }
+    }
+
+    /// The number of lines in the source.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the position of the given byte offset. Offsets past the end of
+    /// the source are clamped to the end.
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.len);
+        let line_idx = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line_idx];
+        let column = offset - line_start;
+        let utf16_column =
+            column - (self.adjustment_before(offset) - self.adjustment_before(line_start));
+        Position {
+            line: line_idx + 1,
+            column: column + 1,
+            utf16_column: utf16_column + 1,
+        }
+    }
+
+    /// Returns the span of the given 1-based line, including its line
+    /// terminator if there is one. Returns `None` if the line doesn't exist.
+    pub fn line_span(&self, line: usize) -> Option<Span> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self.line_starts.get(line).copied().unwrap_or(self.len);
+        Some(Span(start, end))
+    }
+
+    // The total UTF-8/UTF-16 length difference of all characters before the
+    // given byte offset.
+    fn adjustment_before(&self, offset: usize) -> usize {
+        let idx = self.wide_chars.partition_point(|&(o, _)| o < offset);
+        if idx == 0 {
+            0
+        } else {
+            self.wide_chars[idx - 1].1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let source = "a\n\r\nbé𝄞c\nd";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 4);
+
+        let examples = [
+            (0, (1, 1, 1)),
+            (1, (1, 2, 2)),
+            (2, (2, 1, 1)),
+            (3, (2, 2, 2)),
+            (4, (3, 1, 1)),
+            (5, (3, 2, 2)),
+            (7, (3, 4, 3)),
+            (11, (3, 8, 5)),
+            (12, (3, 9, 6)),
+            (13, (4, 1, 1)),
+            (14, (4, 2, 2)),
+            (100, (4, 2, 2)),
+        ];
+        for (offset, (line, column, utf16_column)) in examples {
+            assert_eq!(
+                index.position(offset),
+                Position {
+                    line,
+                    column,
+                    utf16_column
+                },
+                "position of offset {}",
+                offset
+            );
+        }
+
+        assert_eq!(index.line_span(1), Some(Span(0, 2)));
+        assert_eq!(index.line_span(3), Some(Span(4, 13)));
+        assert_eq!(index.line_span(4), Some(Span(13, 14)));
+        assert_eq!(index.line_span(0), None);
+        assert_eq!(index.line_span(5), None);
+    }
+
+    #[test]
+    fn test_carriage_returns() {
+        let source = "a\rb\r\n\rc";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 4);
+
+        let positions = [(1, 1), (1, 2), (2, 1), (2, 2), (2, 3), (3, 1), (4, 1)];
+        for (offset, expected) in positions.into_iter().enumerate() {
+            let position = index.position(offset);
+            assert_eq!(
+                (position.line, position.column),
+                expected,
+                "offset {}",
+                offset
+            );
+        }
+
+        assert_eq!(index.line_span(1), Some(Span(0, 2)));
+        assert_eq!(index.line_span(2), Some(Span(2, 5)));
+        assert_eq!(index.line_span(3), Some(Span(5, 6)));
+        assert_eq!(index.line_span(4), Some(Span(6, 7)));
+    }
+}
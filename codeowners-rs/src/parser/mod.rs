@@ -0,0 +1,837 @@
+mod formatter;
+mod line_index;
+mod printer;
+mod validator;
+
+use std::{fs::File, io::Read, path::Path};
+
+use crate::ruleset::{self, Owner, OwnerPolicy};
+
+pub use self::formatter::{format, FormatOptions};
+pub use self::line_index::{LineIndex, Position};
+pub use self::printer::print;
+pub use self::validator::{validate, Diagnostic, Severity, MAX_FILE_SIZE};
+
+/// Parse a CODEOWNERS file from a string, returning a `ParseResult` containing
+/// the parsed rules and any errors encountered.
+pub fn parse(source: &str) -> ParseResult {
+    Parser::new(source).parse()
+}
+
+/// Parse a CODEOWNERS file from a string using the given `ParseOptions`. This
+/// is useful for parsing files in dialects other than GitHub's, such as
+/// GitLab's, which supports sections.
+pub fn parse_with_options(source: &str, options: &ParseOptions) -> ParseResult {
+    Parser::with_options(source, options).parse()
+}
+
+/// Parse a CODEOWNERS file from a file path, reading the contents of the file
+/// and returning a `ParseResult` containing the parsed rules and any errors
+/// encountered.
+pub fn parse_file(path: &Path) -> std::io::Result<ParseResult> {
+    let mut file = File::open(path)?;
+    let mut source = String::new();
+    file.read_to_string(&mut source)?;
+    Ok(parse(&source))
+}
+
+/// Options that control how a CODEOWNERS file is parsed.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub dialect: Dialect,
+    /// Which owners are valid. Owners that aren't valid under this policy are
+    /// reported as errors.
+    pub owner_policy: OwnerPolicy,
+}
+
+/// The flavour of CODEOWNERS syntax to parse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /// GitHub's CODEOWNERS syntax.
+    #[default]
+    GitHub,
+    /// GitLab's CODEOWNERS syntax, which extends GitHub's with section headers
+    /// such as `[Section]`, `^[Optional Section]` and `[Section][2] @owner`.
+    GitLab,
+}
+
+/// The result of parsing a CODEOWNERS file. Contains a `Vec` of parsed rules
+/// and a `Vec` of errors encountered during parsing. If the `Vec` of errors is
+/// empty, the file was parsed successfully. Otherwise, the parser skips any
+/// line it can't make sense of and continues with the next one, so the `Vec`
+/// of rules contains every rule that could be parsed, and the `Vec` of errors
+/// contains every problem found in the file.
+///
+/// Section headers are only recognised when parsing the GitLab dialect, so
+/// `sections` will always be empty for GitHub CODEOWNERS files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseResult {
+    pub rules: Vec<Rule>,
+    pub sections: Vec<Section>,
+    pub errors: Vec<ParseError>,
+    pub trailing_comments: Vec<Spanned<String>>,
+}
+
+impl ParseResult {
+    /// Convert the `ParseResult` into a `RuleSet`. If the `ParseResult` contains
+    /// any errors, they are ignored. Rules without owners inherit the default
+    /// owners of the section they belong to.
+    pub fn into_ruleset(self: ParseResult) -> ruleset::RuleSet {
+        let mut rules: Vec<ruleset::Rule> = self.rules.into_iter().map(|r| r.into()).collect();
+
+        let mut sections = Vec::with_capacity(self.sections.len());
+        let mut section_iter = self.sections.into_iter().peekable();
+        while let Some(section) = section_iter.next() {
+            let end = section_iter
+                .peek()
+                .map(|next| next.first_rule)
+                .unwrap_or(rules.len());
+            let section = ruleset::Section {
+                name: section.name.0,
+                optional: section.optional,
+                approvals: section.approvals.map(|a| a.0),
+                default_owners: section.default_owners.into_iter().map(|o| o.0).collect(),
+                rules: section.first_rule..end,
+            };
+            for rule in &mut rules[section.rules.clone()] {
+                if rule.owners.is_empty() {
+                    rule.owners = section.default_owners.clone();
+                }
+            }
+            sections.push(section);
+        }
+
+        ruleset::RuleSet::with_sections(rules, sections)
+    }
+}
+
+/// A parsed CODEOWNERS rule. Contains a pattern and a list of owners, along
+/// with any comments that were found before or after the rule. All fields are
+/// wrapped in `Spanned` to preserve the original source location.
+///
+/// For most uses, the `Rule` type should be converted into a `ruleset::Rule`
+/// using the `From` trait or the `into_ruleset` method on `ParseResult`. This
+/// will remove the `Spanned` wrappers and discard any comments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub pattern: Spanned<String>,
+    pub owners: Vec<Spanned<Owner>>,
+    pub leading_comments: Vec<Spanned<String>>,
+    pub trailing_comment: Option<Spanned<String>>,
+}
+
+impl Rule {
+    fn new(pattern: Spanned<String>, owners: Vec<Spanned<Owner>>) -> Rule {
+        Rule {
+            pattern,
+            owners,
+            leading_comments: Vec::new(),
+            trailing_comment: None,
+        }
+    }
+
+    /// Returns the span of the rule, from the start of the pattern to the end
+    /// of the last owner or trailing comment. Leading comments aren't included.
+    pub fn span(&self) -> Span {
+        let end = match (&self.trailing_comment, self.owners.last()) {
+            (Some(comment), _) => comment.1 .1,
+            (None, Some(owner)) => owner.1 .1,
+            (None, None) => self.pattern.1 .1,
+        };
+        Span(self.pattern.1 .0, end)
+    }
+}
+
+impl From<Rule> for ruleset::Rule {
+    fn from(rule: Rule) -> Self {
+        ruleset::Rule {
+            pattern: rule.pattern.0,
+            owners: rule.owners.into_iter().map(|o| o.0).collect(),
+        }
+    }
+}
+
+/// A GitLab CODEOWNERS section header, such as `^[Docs][2] @docs-team`. Rules
+/// following the header, up to the next section header, belong to the section.
+///
+/// `span` covers the header itself (from the optional `^` to the closing
+/// bracket of the approval count, if present), while `name` covers just the
+/// text between the first pair of brackets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: Spanned<String>,
+    pub optional: bool,
+    pub approvals: Option<Spanned<usize>>,
+    pub default_owners: Vec<Spanned<Owner>>,
+    pub leading_comments: Vec<Spanned<String>>,
+    pub trailing_comment: Option<Spanned<String>>,
+    pub span: Span,
+    /// The index in `ParseResult::rules` of the first rule in this section.
+    pub first_rule: usize,
+}
+
+/// An error encountered while parsing a CODEOWNERS file. Contains a message
+/// describing the error and a `Span` indicating the location of the error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl ParseError {
+    fn new(message: impl Into<String>, span: impl Into<Span>) -> ParseError {
+        ParseError {
+            message: message.into(),
+            span: span.into(),
+        }
+    }
+
+    /// Returns the line and column where the error starts.
+    pub fn start_position(&self, index: &LineIndex) -> Position {
+        self.span.start_position(index)
+    }
+
+    /// Returns the line and column where the error ends.
+    pub fn end_position(&self, index: &LineIndex) -> Position {
+        self.span.end_position(index)
+    }
+}
+
+/// A span of text in a CODEOWNERS file. Contains the start and end byte offsets
+/// of the span. A [`LineIndex`] can be used to convert the offsets into line
+/// and column positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span(pub usize, pub usize);
+
+impl Span {
+    /// Returns the line and column of the start of the span.
+    pub fn start_position(&self, index: &LineIndex) -> Position {
+        index.position(self.0)
+    }
+
+    /// Returns the line and column of the end of the span.
+    pub fn end_position(&self, index: &LineIndex) -> Position {
+        index.position(self.1)
+    }
+}
+
+impl From<(usize, usize)> for Span {
+    fn from((start, end): (usize, usize)) -> Self {
+        Span(start, end)
+    }
+}
+
+/// A wrapper around a value that preserves the original source location of the
+/// value. Contains the value and a `Span` indicating the location of the value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spanned<T>(pub T, pub Span);
+
+impl<T> Spanned<T> {
+    fn new(val: impl Into<T>, span: impl Into<Span>) -> Spanned<T> {
+        Spanned(val.into(), span.into())
+    }
+
+    /// Returns the line and column where the value starts.
+    pub fn start_position(&self, index: &LineIndex) -> Position {
+        self.1.start_position(index)
+    }
+
+    /// Returns the line and column where the value ends.
+    pub fn end_position(&self, index: &LineIndex) -> Position {
+        self.1.end_position(index)
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+    errors: Vec<ParseError>,
+    dialect: Dialect,
+    owner_policy: OwnerPolicy,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self::with_options(source, &ParseOptions::default())
+    }
+
+    fn with_options(source: &'a str, options: &ParseOptions) -> Self {
+        Self {
+            source,
+            pos: 0,
+            errors: Vec::new(),
+            dialect: options.dialect,
+            owner_policy: options.owner_policy.clone(),
+        }
+    }
+
+    fn parse(mut self) -> ParseResult {
+        let mut rules = Vec::new();
+        let mut sections = Vec::new();
+        let mut leading_comments = Vec::new();
+
+        // Recoverable errors are added to self.errors during parsing. Errors
+        // that prevent the rest of a line from being parsed are passed via
+        // results, in which case we skip to the next line and carry on.
+        self.skip_whitespace();
+        while let Some(c) = self.peek() {
+            match c {
+                '\r' | '\n' => {
+                    self.next();
+                }
+                '#' => {
+                    let comment = self.parse_comment();
+                    leading_comments.push(comment);
+                }
+                '[' | '^' if self.at_section_header() => {
+                    match self.parse_section() {
+                        Ok(mut section) => {
+                            section.leading_comments = leading_comments;
+                            section.first_rule = rules.len();
+                            sections.push(section);
+                        }
+                        Err(e) => {
+                            self.errors.push(e);
+                            self.skip_line();
+                        }
+                    }
+                    leading_comments = Vec::new();
+                }
+                _ => {
+                    match self.parse_rule() {
+                        Ok(mut rule) => {
+                            rule.leading_comments = leading_comments;
+                            rules.push(rule)
+                        }
+                        Err(e) => {
+                            self.errors.push(e);
+                            self.skip_line();
+                        }
+                    }
+                    leading_comments = Vec::new();
+                }
+            }
+            self.skip_whitespace();
+        }
+
+        ParseResult {
+            rules,
+            sections,
+            errors: self.errors,
+            trailing_comments: leading_comments,
+        }
+    }
+
+    // Section headers only exist in the GitLab dialect. They start with `[`, or
+    // `^[` for optional sections. Patterns that need to start with `[` can be
+    // escaped with a backslash.
+    fn at_section_header(&self) -> bool {
+        if self.dialect != Dialect::GitLab {
+            return false;
+        }
+        let mut chars = self.source[self.pos..].chars();
+        match chars.next() {
+            Some('[') => true,
+            Some('^') => chars.next() == Some('['),
+            _ => false,
+        }
+    }
+
+    fn parse_section(&mut self) -> Result<Section, ParseError> {
+        let start = self.pos;
+        let optional = self.peek() == Some('^');
+        if optional {
+            self.next();
+        }
+
+        let name = self.parse_bracketed()?;
+        if name.0.trim().is_empty() {
+            return Err(ParseError::new("expected section name", name.1));
+        }
+
+        let approvals = if self.peek() == Some('[') {
+            let approvals = self.parse_bracketed()?;
+            match approvals.0.parse::<usize>() {
+                Ok(n) => Some(Spanned::new(n, approvals.1)),
+                Err(_) => {
+                    return Err(ParseError::new(
+                        format!("invalid approval count: {}", approvals.0),
+                        approvals.1,
+                    ))
+                }
+            }
+        } else {
+            None
+        };
+        let span = Span(start, self.pos);
+
+        let mut default_owners = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.parse_owner() {
+                Ok(Some(owner)) => default_owners.push(owner),
+                Ok(None) => break,
+                Err(err) => self.errors.push(err),
+            }
+        }
+
+        let trailing_comment = match self.peek() {
+            Some('\r' | '\n') | None => None,
+            Some('#') => Some(self.parse_comment()),
+            _ => return Err(ParseError::new("expected newline", (self.pos, self.pos))),
+        };
+
+        Ok(Section {
+            name,
+            optional,
+            approvals,
+            default_owners,
+            leading_comments: Vec::new(),
+            trailing_comment,
+            span,
+            first_rule: 0,
+        })
+    }
+
+    // Parse the contents of a `[...]` group, returning the text between the
+    // brackets. The span covers the contents but not the brackets themselves.
+    fn parse_bracketed(&mut self) -> Result<Spanned<String>, ParseError> {
+        if self.next() != Some('[') {
+            return Err(ParseError::new("expected `[`", (self.pos, self.pos)));
+        }
+        let start = self.pos;
+        let mut contents = String::new();
+        loop {
+            match self.peek() {
+                Some(']') => break,
+                Some('\r' | '\n') | None => {
+                    return Err(ParseError::new("expected `]`", (self.pos, self.pos)))
+                }
+                Some(c) => {
+                    contents.push(c);
+                    self.next();
+                }
+            }
+        }
+        let span = Span(start, self.pos);
+        self.next();
+        Ok(Spanned(contents, span))
+    }
+
+    fn parse_comment(&mut self) -> Spanned<String> {
+        let start = self.pos;
+        let mut comment = String::new();
+        loop {
+            match self.peek() {
+                Some('\r' | '\n') => break,
+                Some(c) => {
+                    self.next();
+                    comment.push(c);
+                }
+                None => break,
+            }
+        }
+        Spanned::new(comment, (start, self.pos))
+    }
+
+    fn parse_rule(&mut self) -> Result<Rule, ParseError> {
+        let pattern = self.parse_pattern();
+        if pattern.0.is_empty() {
+            return Err(ParseError::new("expected pattern", (self.pos, self.pos)));
+        }
+
+        let mut owners = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.parse_owner() {
+                Ok(Some(owner)) => owners.push(owner),
+                Ok(None) => break,
+                Err(err) => self.errors.push(err),
+            }
+        }
+
+        // Find pattern terminator (newline, EOF, or #)
+        match self.peek() {
+            Some('\r' | '\n') | None => Ok(Rule::new(pattern, owners)),
+            Some('#') => {
+                let trailing_comment = Some(self.parse_comment());
+                Ok(Rule {
+                    pattern,
+                    owners,
+                    leading_comments: vec![],
+                    trailing_comment,
+                })
+            }
+            _ => Err(ParseError::new("expected newline", (self.pos, self.pos))),
+        }
+    }
+
+    fn parse_pattern(&mut self) -> Spanned<String> {
+        let start = self.pos;
+        let mut pattern = String::new();
+        let mut escaped = false;
+        loop {
+            match self.peek() {
+                Some('\\') if !escaped => {
+                    escaped = true;
+                    self.next();
+                }
+                // A backslash can't escape a line break, so a pattern never
+                // spills onto the next line
+                Some('\r' | '\n') => break,
+                Some(' ' | '\t' | '#') if !escaped => break,
+                Some(c) => {
+                    if c == '\0' {
+                        self.errors.push(ParseError::new(
+                            "patterns cannot contain null bytes",
+                            (self.pos, self.pos + 1),
+                        ));
+                    }
+                    pattern.push(c);
+                    self.next();
+                    escaped = false;
+                }
+                None => break,
+            }
+        }
+        Spanned::new(pattern, (start, self.pos))
+    }
+
+    fn parse_owner(&mut self) -> Result<Option<Spanned<Owner>>, ParseError> {
+        let start = self.pos;
+        let mut owner_str = String::new();
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '#' | '\r' | '\n') => break,
+                Some(c) => {
+                    owner_str.push(c);
+                    self.next();
+                }
+                None => break,
+            }
+        }
+
+        if owner_str.is_empty() {
+            return Ok(None);
+        }
+
+        match self.owner_policy.parse_owner(owner_str) {
+            Ok(owner) => Ok(Some(Spanned::new(owner, (start, self.pos)))),
+            Err(err) => Err(ParseError {
+                message: err.to_string(),
+                span: (start, self.pos).into(),
+            }),
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '\r' || c == '\n' {
+                break;
+            }
+            self.next();
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t') = self.peek() {
+            self.next();
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ruleset::OwnerKind;
+    use super::*;
+
+    #[test]
+    fn test_parser() {
+        let examples = vec![
+            (
+                "foo",
+                vec![Rule::new(Spanned::new("foo", (0, 3)), vec![])],
+                vec![],
+                vec![],
+            ),
+            (
+                "foo\\  ",
+                vec![Rule::new(Spanned::new("foo ", (0, 5)), vec![])],
+                vec![],
+                vec![],
+            ),
+            (
+                " foo ",
+                vec![Rule::new(Spanned::new("foo", (1, 4)), vec![])],
+                vec![],
+                vec![],
+            ),
+            (
+                "foo\nbar\r\n \nbaz",
+                vec![
+                    Rule::new(Spanned::new("foo", (0, 3)), vec![]),
+                    Rule::new(Spanned::new("bar", (4, 7)), vec![]),
+                    Rule::new(Spanned::new("baz", (11, 14)), vec![]),
+                ],
+                vec![],
+                vec![],
+            ),
+            (
+                "f\0oo",
+                vec![Rule::new(Spanned::new("f\0oo", (0, 4)), vec![])],
+                vec![ParseError::new(
+                    "patterns cannot contain null bytes",
+                    (1, 2),
+                )],
+                vec![],
+            ),
+            (
+                "foo bar @baz",
+                vec![Rule::new(
+                    Spanned::new("foo", (0, 3)),
+                    vec![Spanned::new(
+                        Owner::new("@baz".to_string(), OwnerKind::User),
+                        (8, 12),
+                    )],
+                )],
+                vec![ParseError::new("invalid owner: bar", (4, 7))],
+                vec![],
+            ),
+            (
+                "foo#abc",
+                vec![Rule {
+                    pattern: Spanned::new("foo", (0, 3)),
+                    owners: Default::default(),
+                    leading_comments: Default::default(),
+                    trailing_comment: Some(Spanned::new("#abc", (3, 7))),
+                }],
+                vec![],
+                vec![],
+            ),
+            (
+                "foo\n#abc",
+                vec![Rule::new(Spanned::new("foo", (0, 3)), vec![])],
+                vec![],
+                vec![Spanned::new("#abc", (4, 8))],
+            ),
+            (
+                "foo @bar",
+                vec![Rule::new(
+                    Spanned::new("foo", (0, 3)),
+                    vec![Spanned::new(
+                        Owner::new("@bar".to_string(), OwnerKind::User),
+                        (4, 8),
+                    )],
+                )],
+                vec![],
+                vec![],
+            ),
+            (
+                "a/b @c/d e@f.co",
+                vec![Rule::new(
+                    Spanned::new("a/b", (0, 3)),
+                    vec![
+                        Spanned::new(Owner::new("@c/d".to_string(), OwnerKind::Team), (4, 8)),
+                        Spanned::new(Owner::new("e@f.co".to_string(), OwnerKind::Email), (9, 15)),
+                    ],
+                )],
+                vec![],
+                vec![],
+            ),
+            (
+                "\n foo @bar# baz \n",
+                vec![Rule {
+                    pattern: Spanned::new("foo", (2, 5)),
+                    owners: vec![Spanned::new(
+                        Owner::new("@bar".to_string(), OwnerKind::User),
+                        (6, 10),
+                    )],
+                    leading_comments: Default::default(),
+                    trailing_comment: Some(Spanned::new("# baz ", (10, 16))),
+                }],
+                vec![],
+                vec![],
+            ),
+            (
+                "# a\nfoo # b\n# c\n# d\n\nbar\n",
+                vec![
+                    Rule {
+                        pattern: Spanned::new("foo", (4, 7)),
+                        owners: vec![],
+                        leading_comments: vec![Spanned::new("# a", (0, 3))],
+                        trailing_comment: Some(Spanned::new("# b", (8, 11))),
+                    },
+                    Rule {
+                        pattern: Spanned::new("bar", (21, 24)),
+                        owners: vec![],
+                        leading_comments: vec![
+                            Spanned::new("# c", (12, 15)),
+                            Spanned::new("# d", (16, 19)),
+                        ],
+                        trailing_comment: None,
+                    },
+                ],
+                vec![],
+                vec![],
+            ),
+        ];
+
+        for (source, rules, errors, trailing_comments) in examples {
+            assert_eq!(
+                Parser::new(source).parse(),
+                ParseResult {
+                    rules,
+                    sections: vec![],
+                    errors,
+                    trailing_comments
+                },
+                "result mismatch for `{}`",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_gitlab_sections() {
+        let gitlab = ParseOptions {
+            dialect: Dialect::GitLab,
+            ..Default::default()
+        };
+        let source = "foo @a\n# docs\n^[Docs][2] @b # c\nbar\n[ Empty ]\n";
+        let result = Parser::with_options(source, &gitlab).parse();
+        assert_eq!(result.errors, vec![]);
+        assert_eq!(
+            result.rules,
+            vec![
+                Rule::new(
+                    Spanned::new("foo", (0, 3)),
+                    vec![Spanned::new(
+                        Owner::new("@a".to_string(), OwnerKind::User),
+                        (4, 6)
+                    )],
+                ),
+                Rule::new(Spanned::new("bar", (32, 35)), vec![]),
+            ]
+        );
+        assert_eq!(
+            result.sections,
+            vec![
+                Section {
+                    name: Spanned::new("Docs", (16, 20)),
+                    optional: true,
+                    approvals: Some(Spanned::new(2usize, (22, 23))),
+                    default_owners: vec![Spanned::new(
+                        Owner::new("@b".to_string(), OwnerKind::User),
+                        (25, 27)
+                    )],
+                    leading_comments: vec![Spanned::new("# docs", (7, 13))],
+                    trailing_comment: Some(Spanned::new("# c", (28, 31))),
+                    span: Span(14, 24),
+                    first_rule: 1,
+                },
+                Section {
+                    name: Spanned::new(" Empty ", (37, 44)),
+                    optional: false,
+                    approvals: None,
+                    default_owners: vec![],
+                    leading_comments: vec![],
+                    trailing_comment: None,
+                    span: Span(36, 45),
+                    first_rule: 2,
+                },
+            ]
+        );
+
+        // Section headers aren't recognised in the GitHub dialect
+        let result = Parser::new("[Docs] @b").parse();
+        assert_eq!(result.sections, vec![]);
+        assert_eq!(result.rules[0].pattern, Spanned::new("[Docs]", (0, 6)));
+
+        // Escaped brackets are patterns in the GitLab dialect
+        let result = Parser::with_options("\\[Docs] @b", &gitlab).parse();
+        assert_eq!(result.sections, vec![]);
+        assert_eq!(result.rules[0].pattern, Spanned::new("[Docs]", (0, 7)));
+
+        let examples = [
+            ("[Docs", ParseError::new("expected `]`", (5, 5))),
+            ("[]", ParseError::new("expected section name", (1, 1))),
+            (
+                "[Docs][x]",
+                ParseError::new("invalid approval count: x", (7, 8)),
+            ),
+        ];
+        for (source, error) in examples {
+            let result = Parser::with_options(source, &gitlab).parse();
+            assert_eq!(result.errors, vec![error], "for `{}`", source);
+        }
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        // Rules after a line that can't be parsed are still returned
+        let source = "foo @a\n\\\nbar b@ @c\nbaz\0 @d\nqux @e";
+        let result = Parser::new(source).parse();
+        assert_eq!(
+            result.errors,
+            vec![
+                ParseError::new("expected pattern", (8, 8)),
+                ParseError::new("invalid owner: b@", (13, 15)),
+                ParseError::new("patterns cannot contain null bytes", (22, 23)),
+            ]
+        );
+        assert_eq!(
+            result
+                .rules
+                .iter()
+                .map(|r| (r.pattern.0.as_str(), r.owners.len()))
+                .collect::<Vec<_>>(),
+            vec![("foo", 1), ("bar", 1), ("baz\0", 1), ("qux", 1)]
+        );
+
+        // A trailing backslash doesn't carry a pattern onto the next line
+        let result = Parser::new("foo\\\nbar @a").parse();
+        assert_eq!(result.errors, vec![]);
+        assert_eq!(
+            result
+                .rules
+                .iter()
+                .map(|r| r.pattern.0.as_str())
+                .collect::<Vec<_>>(),
+            vec!["foo", "bar"]
+        );
+
+        let gitlab = ParseOptions {
+            dialect: Dialect::GitLab,
+            ..Default::default()
+        };
+        let source = "[Docs\nfoo @a\n[Docs][x] @b\n[]\nbar @c";
+        let result = Parser::with_options(source, &gitlab).parse();
+        assert_eq!(
+            result.errors,
+            vec![
+                ParseError::new("expected `]`", (5, 5)),
+                ParseError::new("invalid approval count: x", (20, 21)),
+                ParseError::new("expected section name", (27, 27)),
+            ]
+        );
+        assert_eq!(
+            result
+                .rules
+                .iter()
+                .map(|r| r.pattern.0.as_str())
+                .collect::<Vec<_>>(),
+            vec!["foo", "bar"]
+        );
+        assert_eq!(result.sections, vec![]);
+    }
+}
@@ -0,0 +1,321 @@
+use super::{ParseResult, Parser, Section, Span, Spanned};
+
+/// Print a `ParseResult` back out as CODEOWNERS source. `original` should be
+/// the source the `ParseResult` was parsed from (or an empty string for results
+/// built from scratch). It's used to recover the whitespace between syntax
+/// nodes, so for well-formed input `print(&parse(source), source) == source`.
+///
+/// The `ParseResult` may be edited before printing. Nodes whose span no longer
+/// points at matching text in `original` (for instance, rules that have been
+/// added or modified) are printed with canonical formatting: one space between
+/// the components of a rule, one rule per line, and patterns escaped as
+/// necessary. Everything else keeps its original formatting. Nodes that were
+/// modified in place keep the whitespace that preceded them, so blank lines
+/// and alignment survive edits.
+///
+/// # Example
+/// ```
+/// use codeowners_rs::{parse, print};
+///
+/// let source = "# Docs\n/docs/   @docs-team  # the docs\n\n*.rs @rustaceans\n";
+/// let mut result = parse(source);
+/// assert_eq!(print(&result, source), source);
+///
+/// result.rules[1].pattern.0 = "src/my file.rs".to_string();
+/// assert_eq!(
+///     print(&result, source),
+///     "# Docs\n/docs/   @docs-team  # the docs\n\nsrc/my\\ file.rs @rustaceans\n"
+/// );
+/// ```
+pub fn print(result: &ParseResult, original: &str) -> String {
+    let mut printer = Printer::new(original);
+
+    let mut sections = result.sections.iter().peekable();
+    for (idx, rule) in result.rules.iter().enumerate() {
+        while let Some(section) = sections.next_if(|s| s.first_rule <= idx) {
+            printer.section(section);
+        }
+        printer.comments(&rule.leading_comments);
+        printer.pattern(&rule.pattern);
+        for owner in &rule.owners {
+            printer.inline(&owner.0.value, &owner.1);
+        }
+        if let Some(comment) = &rule.trailing_comment {
+            printer.inline(&comment.0, &comment.1);
+        }
+    }
+    for section in sections {
+        printer.section(section);
+    }
+    printer.comments(&result.trailing_comments);
+
+    printer.finish()
+}
+
+struct Printer<'a> {
+    original: &'a str,
+    output: String,
+    // The end of the span of the last node that was printed, if that span
+    // points into the original source.
+    last_end: Option<usize>,
+    // Whether any nodes have been printed yet.
+    printed_any: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn new(original: &'a str) -> Self {
+        Self {
+            original,
+            output: String::new(),
+            last_end: None,
+            printed_any: false,
+        }
+    }
+
+    fn comments(&mut self, comments: &[Spanned<String>]) {
+        for comment in comments {
+            self.line_start(&comment.0, &comment.1);
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Spanned<String>) {
+        let text = pattern_source(pattern, self.original);
+        self.write(&text, &pattern.1, true);
+    }
+
+    fn section(&mut self, section: &Section) {
+        self.comments(&section.leading_comments);
+
+        self.line_start(&section_header(section), &section.span);
+
+        for owner in &section.default_owners {
+            self.inline(&owner.0.value, &owner.1);
+        }
+        if let Some(comment) = &section.trailing_comment {
+            self.inline(&comment.0, &comment.1);
+        }
+    }
+
+    // Print a node that starts a new line.
+    fn line_start(&mut self, text: &str, span: &Span) {
+        self.write(text, span, true);
+    }
+
+    // Print a node that follows another node on the same line.
+    fn inline(&mut self, text: &str, span: &Span) {
+        self.write(text, span, false);
+    }
+
+    fn write(&mut self, text: &str, span: &Span, line_start: bool) {
+        let original = self.original_text(span);
+        let whitespace = match original {
+            Some(_) => self.whitespace_before(span.0),
+            None => "",
+        };
+        if line_start {
+            if self.printed_any && !whitespace.contains(['\r', '\n']) {
+                self.output.push('\n');
+            }
+            self.output.push_str(whitespace);
+        } else if whitespace.contains(['\r', '\n'])
+            || (whitespace.is_empty() && self.last_end != Some(span.0))
+        {
+            // Nodes on the same line need separating, unless they were
+            // adjacent in the original source (e.g. a trailing comment
+            // directly after a pattern).
+            self.output.push(' ');
+        } else {
+            self.output.push_str(whitespace);
+        }
+        self.output.push_str(text);
+        self.last_end = original.map(|_| span.1);
+        self.printed_any = true;
+    }
+
+    fn finish(mut self) -> String {
+        // Keep any whitespace that followed the last node, or that makes up the
+        // whole file if there are no nodes.
+        let end = match self.last_end {
+            Some(end) => end,
+            None if self.printed_any => {
+                self.output.push('\n');
+                return self.output;
+            }
+            None => 0,
+        };
+        let rest = &self.original[end..];
+        let trailing = rest.trim_start_matches(is_whitespace);
+        self.output.push_str(&rest[..rest.len() - trailing.len()]);
+        self.output
+    }
+
+    // The text at the given span in the original source, if the span points
+    // into the original source.
+    fn original_text(&self, span: &Span) -> Option<&'a str> {
+        if span.0 >= span.1 {
+            return None;
+        }
+        self.original.get(span.0..span.1)
+    }
+
+    // The whitespace immediately before the given offset in the original
+    // source, stopping at the end of the previously printed node.
+    fn whitespace_before(&self, offset: usize) -> &'a str {
+        let before = &self.original[..offset];
+        let mut start = before.trim_end_matches(is_whitespace).len();
+        if let Some(last_end) = self.last_end.filter(|&end| end <= offset) {
+            start = start.max(last_end);
+        }
+        &before[start..]
+    }
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n')
+}
+
+// The source text for a pattern. If the pattern's span points at text in the
+// original source that unescapes to the pattern, we keep the original text (and
+// therefore its escaping). Otherwise we escape the pattern ourselves.
+pub(super) fn pattern_source(pattern: &Spanned<String>, original: &str) -> String {
+    let span = &pattern.1;
+    let text = original.get(span.0..span.1).filter(|text| {
+        let mut parser = Parser::new(text);
+        !text.is_empty() && parser.parse_pattern().0 == pattern.0 && parser.pos == text.len()
+    });
+    match text {
+        Some(text) => text.to_owned(),
+        None => escape_pattern(&pattern.0),
+    }
+}
+
+// The source text for a section header, such as `^[Docs][2]`.
+pub(super) fn section_header(section: &Section) -> String {
+    let mut header = String::new();
+    if section.optional {
+        header.push('^');
+    }
+    header.push('[');
+    header.push_str(&section.name.0);
+    header.push(']');
+    if let Some(approvals) = &section.approvals {
+        header.push_str(&format!("[{}]", approvals.0));
+    }
+    header
+}
+
+// Escape characters that would otherwise terminate a pattern, or cause it to
+// be parsed as a GitLab section header. Line breaks always end a pattern, even
+// after a backslash, so they're left as they are.
+fn escape_pattern(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    for (i, c) in pattern.char_indices() {
+        let section_start = i == 0 && (c == '[' || (c == '^' && pattern[1..].starts_with('[')));
+        if section_start || matches!(c, ' ' | '\t' | '#' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parser::{parse_with_options, Dialect, ParseOptions, Rule},
+        ruleset::{Owner, OwnerKind},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let examples = [
+            "",
+            "\n\n",
+            "foo",
+            "foo\n",
+            "foo @a\nbar @b @c\n",
+            "  foo   @a\t@b   \n\n\n  # comment  \nbar\n",
+            "foo\\ bar\\#baz\\\\ @a #comment\n",
+            "foo\\  @a",
+            "foo#abc\n",
+            "# leading\n\n# more\nfoo @a # trailing\n\n# trailing comments\n",
+            "foo @a\r\nbar @b\r\n",
+            "foo @a\rbar @b\r",
+            "foo\\\nbar @a\n",
+            "foo\\\r\nbar\\\r",
+            "*.rs @a/b e@f.co\n/docs/**/*.md\n",
+        ];
+        for source in examples {
+            let result = crate::parse(source);
+            assert_eq!(print(&result, source), source);
+        }
+
+        let gitlab = ParseOptions {
+            dialect: Dialect::GitLab,
+            ..Default::default()
+        };
+        let source = "foo @a\n\n# docs\n^[Docs][2]  @b # c\nbar\n[Empty]\n\\[x]\n";
+        let result = parse_with_options(source, &gitlab);
+        assert_eq!(print(&result, source), source);
+    }
+
+    #[test]
+    fn test_edits() {
+        let source = "# a\nfoo   @a # x\n\n# b\nbar @b\nbaz @c\n";
+        let mut result = crate::parse(source);
+
+        // Removing a rule keeps the formatting of the surrounding rules
+        let mut removed = result.clone();
+        removed.rules.remove(1);
+        assert_eq!(print(&removed, source), "# a\nfoo   @a # x\nbaz @c\n");
+
+        // New and modified nodes are printed canonically
+        result.rules[0].owners.push(Spanned(
+            Owner::new("@d".to_string(), OwnerKind::User),
+            Span(0, 0),
+        ));
+        result.rules[2].pattern = Spanned("[b z]#".to_string(), Span(0, 0));
+        result.rules.push(Rule {
+            pattern: Spanned("new".to_string(), Span(0, 0)),
+            owners: vec![],
+            leading_comments: vec![Spanned("# new".to_string(), Span(0, 0))],
+            trailing_comment: None,
+        });
+        assert_eq!(
+            print(&result, source),
+            "# a\nfoo   @a @d # x\n\n# b\nbar @b\n\\[b\\ z]\\# @c\n# new\nnew\n"
+        );
+
+        // Results built from scratch don't need an original source
+        let result = ParseResult {
+            rules: vec![Rule {
+                pattern: Spanned("foo".to_string(), Span(0, 0)),
+                owners: vec![Spanned(
+                    Owner::new("@a".to_string(), OwnerKind::User),
+                    Span(0, 0),
+                )],
+                leading_comments: vec![],
+                trailing_comment: Some(Spanned("# x".to_string(), Span(0, 0))),
+            }],
+            sections: vec![],
+            errors: vec![],
+            trailing_comments: vec![],
+        };
+        assert_eq!(print(&result, ""), "foo @a # x\n");
+
+        // Line breaks can't be escaped, so a pattern containing one is printed
+        // as is, and parses as two rules like it would in a CODEOWNERS file
+        let mut result = crate::parse("foo @a\n");
+        result.rules[0].pattern = Spanned("x\\y\nz".to_string(), Span(0, 0));
+        let printed = print(&result, "foo @a\n");
+        assert_eq!(printed, "x\\\\y\nz @a\n");
+        let patterns = crate::parse(&printed)
+            .rules
+            .into_iter()
+            .map(|rule| rule.pattern.0)
+            .collect::<Vec<_>>();
+        assert_eq!(patterns, ["x\\y", "z"]);
+    }
+}
@@ -0,0 +1,162 @@
+use super::{LineIndex, ParseResult, Position, Span};
+
+/// The maximum size of a CODEOWNERS file that GitHub will load.
+pub const MAX_FILE_SIZE: usize = 3 * 1024 * 1024;
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Syntax that may not behave as expected on GitHub.
+    Warning,
+    /// Syntax that GitHub doesn't support. Rules with errors are ignored by
+    /// GitHub, so the files they match may end up without owners.
+    Error,
+}
+
+/// A problem found by [`validate`], with a message and the location of the
+/// problem in the CODEOWNERS file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, message: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Returns the line and column where the problem starts.
+    pub fn start_position(&self, index: &LineIndex) -> Position {
+        self.span.start_position(index)
+    }
+
+    /// Returns the line and column where the problem ends.
+    pub fn end_position(&self, index: &LineIndex) -> Position {
+        self.span.end_position(index)
+    }
+}
+
+/// Check a parsed CODEOWNERS file for syntax that GitHub doesn't support.
+/// The parser accepts gitignore-style syntax that GitHub silently ignores, so
+/// rules using it don't take effect and the files they match may be left
+/// without owners. `source` should be the source the `ParseResult` was parsed
+/// from. Diagnostics are returned in source order.
+///
+/// The following are reported as errors:
+/// - files larger than 3 MB, which GitHub won't load
+/// - patterns starting with `!`, as negation isn't supported
+/// - patterns containing `[`, as character ranges aren't supported
+/// - patterns starting with `\#`, as escaping a leading `#` isn't supported
+///
+/// Other backslash escapes are reported as warnings, as GitHub's handling of
+/// them isn't documented.
+///
+/// # Example
+/// ```
+/// use codeowners_rs::{parse, parser::{validate, Severity}};
+///
+/// let source = "!*.md @docs\n";
+/// let diagnostics = validate(&parse(source), source);
+/// assert_eq!(diagnostics[0].severity, Severity::Error);
+/// ```
+pub fn validate(result: &ParseResult, source: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    if source.len() > MAX_FILE_SIZE {
+        diagnostics.push(Diagnostic::new(
+            Severity::Error,
+            "CODEOWNERS files larger than 3 MB aren't loaded by GitHub",
+            Span(0, 0),
+        ));
+    }
+
+    for rule in &result.rules {
+        let span = &rule.pattern.1;
+        let Some(text) = source.get(span.0..span.1) else {
+            continue;
+        };
+        let at = |start: usize, len: usize| Span(span.0 + start, span.0 + start + len);
+
+        if text.starts_with('!') {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                "negated patterns aren't supported by GitHub, so this rule is ignored",
+                at(0, 1),
+            ));
+        }
+        if text.starts_with("\\#") {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                "escaping a leading `#` isn't supported by GitHub, so this rule is ignored",
+                at(0, 2),
+            ));
+        }
+
+        let mut chars = text.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    let escaped = chars.next();
+                    if i > 0 || escaped.map(|(_, c)| c) != Some('#') {
+                        let len = 1 + escaped.map_or(0, |(_, c)| c.len_utf8());
+                        diagnostics.push(Diagnostic::new(
+                            Severity::Warning,
+                            "backslash escapes may not be supported by GitHub",
+                            at(i, len),
+                        ));
+                    }
+                }
+                '[' => {
+                    let len = text[i..].find(']').map_or(1, |end| end + 1);
+                    diagnostics.push(Diagnostic::new(
+                        Severity::Error,
+                        "character ranges aren't supported by GitHub, so this rule is ignored",
+                        at(i, len),
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let source = "\
+*.rs @a
+!*.md @b
+\\#foo @c
+/src/[ab]*.js @d
+my\\ file.txt @e
+";
+        let result = crate::parse(source);
+        let diagnostics = validate(&result, source)
+            .into_iter()
+            .map(|d| (d.severity, &source[d.span.0..d.span.1]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (Severity::Error, "!"),
+                (Severity::Error, "\\#"),
+                (Severity::Error, "[ab]"),
+                (Severity::Warning, "\\ "),
+            ]
+        );
+
+        let large = "a".repeat(MAX_FILE_SIZE + 1);
+        let diagnostics = validate(&crate::parse(&large), &large);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
+}
@@ -22,7 +22,8 @@ impl Builder {
 
     /// Build the `Matcher` from the patterns added to the builder. This will
     /// consume the builder.    
-    pub fn build(self) -> Matcher {
+    pub fn build(mut self) -> Matcher {
+        self.nfa.compile();
         Matcher::new(self.nfa)
     }
 
@@ -94,12 +95,15 @@ impl Builder {
     // Add a regular (non-epsilon) transition from a given state via the
     // provided path segment.
     fn add_transition(&mut self, from_id: StateId, segment: &str) -> StateId {
-        let existing_transition = self
-            .nfa
-            .transitions_from(from_id)
-            .find(|t| t.path_segment == segment && t.target != from_id);
-        if let Some(t) = existing_transition {
-            t.target
+        let state = self.nfa.state(from_id);
+        let existing_target = state.literal_target(segment).or_else(|| {
+            state
+                .wildcard_transitions()
+                .find(|t| t.path_segment == segment && t.target != from_id)
+                .map(|t| t.target)
+        });
+        if let Some(target) = existing_target {
+            target
         } else {
             let state_id = self.nfa.add_state();
             self.nfa
@@ -117,7 +121,8 @@ impl Builder {
         // states mean we require multiple path segments, which violoates the gitignore spec
         let has_existing_transition = self
             .nfa
-            .transitions_from(from_id)
+            .state(from_id)
+            .wildcard_transitions()
             .any(|t| t.path_segment == "*" && t.target == from_id);
         if has_existing_transition {
             return from_id;
@@ -187,7 +192,7 @@ mod tests {
             if state.terminal_for_patterns.is_some() {
                 dot.push_str(&format!("  s{} [shape=doublecircle];\n", state_id));
             }
-            for transition in state.transitions.iter() {
+            for transition in state.transitions() {
                 dot.push_str(&format!(
                     "  s{} -> s{} [label=\"{}\"];\n",
                     state_id, transition.target.0, transition.path_segment
@@ -204,15 +209,19 @@ mod tests {
         dot
     }
 
+    // The transitions of every state, sorted, as literal transitions are kept
+    // in a hash map and come out in no particular order.
     fn transitions_for(nfa: &Nfa) -> Vec<(usize, String, usize)> {
-        nfa.states_iter()
+        let mut transitions = nfa
+            .states_iter()
             .enumerate()
             .flat_map(|(idx, s)| {
-                s.transitions
-                    .iter()
+                s.transitions()
                     .map(|t| (idx, t.path_segment.clone(), t.target.0 as usize))
                     .collect::<Vec<_>>()
             })
-            .collect()
+            .collect::<Vec<_>>();
+        transitions.sort();
+        transitions
     }
 }
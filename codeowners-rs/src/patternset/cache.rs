@@ -0,0 +1,270 @@
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::BuildHasher,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        RwLock,
+    },
+};
+
+use super::nfa::StateId;
+
+/// Statistics about a [`super::Matcher`]'s transition cache, as returned by
+/// [`cache_stats`](fn@super::Matcher::cache_stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of lookups that found an entry in the cache.
+    pub hits: u64,
+    /// The number of lookups that didn't find an entry in the cache.
+    pub misses: u64,
+    /// The number of entries currently in the cache.
+    pub len: usize,
+    /// The maximum number of entries the cache will hold.
+    pub capacity: usize,
+}
+
+// A bounded map from path prefixes to the NFA states reached after matching
+// them. When the cache is full, entries are evicted using the CLOCK algorithm:
+// each entry has a "referenced" bit that's set when the entry is read, and the
+// clock hand sweeps over the entries, clearing set bits, until it finds an
+// entry whose bit is clear. This approximates LRU, but hits only need to set a
+// bit, so they can be served under a read lock.
+//
+// Matchers are cloned for each thread when matching in parallel, and the
+// clones share a cache, so the cache is split into shards by path hash, each
+// with its own lock and counters. Threads looking up different paths rarely
+// touch the same shard, so they don't contend on a single lock.
+pub(super) struct TransitionCache {
+    shards: Box<[Shard]>,
+    hasher: RandomState,
+}
+
+// Aligned to keep each shard's lock and counters on separate cache lines, so
+// threads updating different shards don't slow each other down.
+#[repr(align(128))]
+struct Shard {
+    clock: RwLock<Clock>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct Clock {
+    capacity: usize,
+    index: HashMap<String, usize>,
+    slots: Vec<Slot>,
+    hand: usize,
+}
+
+struct Slot {
+    path: String,
+    states: Vec<StateId>,
+    referenced: AtomicBool,
+}
+
+impl TransitionCache {
+    // The maximum number of shards.
+    const MAX_SHARDS: usize = 64;
+    // The minimum number of entries per shard. Small caches use fewer shards,
+    // as a shard holding only a few entries evicts them before they're reused.
+    const MIN_SHARD_CAPACITY: usize = 64;
+
+    pub(super) fn new(capacity: usize) -> Self {
+        let shard_count = (capacity / Self::MIN_SHARD_CAPACITY).clamp(1, Self::MAX_SHARDS);
+        Self::with_shards(capacity, shard_count)
+    }
+
+    // Create a cache split into the given number of shards. The capacity is
+    // split as evenly as possible between the shards, so the total never
+    // exceeds it.
+    pub(super) fn with_shards(capacity: usize, shard_count: usize) -> Self {
+        assert!(shard_count > 0, "a cache needs at least one shard");
+        let shards = (0..shard_count)
+            .map(|i| Shard {
+                clock: RwLock::new(Clock {
+                    capacity: capacity / shard_count + usize::from(i < capacity % shard_count),
+                    index: HashMap::new(),
+                    slots: Vec::new(),
+                    hand: 0,
+                }),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            })
+            .collect();
+        Self {
+            shards,
+            hasher: RandomState::new(),
+        }
+    }
+
+    fn shard(&self, path: &str) -> &Shard {
+        let hash = self.hasher.hash_one(path) as usize;
+        &self.shards[hash % self.shards.len()]
+    }
+
+    pub(super) fn get(&self, path: &str) -> Option<Vec<StateId>> {
+        let shard = self.shard(path);
+        let clock = shard.clock.read().expect("valid lock");
+        match clock.index.get(path) {
+            Some(&slot_idx) => {
+                shard.hits.fetch_add(1, Ordering::Relaxed);
+                let slot = &clock.slots[slot_idx];
+                // Avoid writing to the slot when the bit is already set, as
+                // frequently used entries are read by many threads at once
+                if !slot.referenced.load(Ordering::Relaxed) {
+                    slot.referenced.store(true, Ordering::Relaxed);
+                }
+                Some(slot.states.clone())
+            }
+            None => {
+                shard.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub(super) fn insert(&self, path: String, states: Vec<StateId>) {
+        self.shard(&path)
+            .clock
+            .write()
+            .expect("valid lock")
+            .insert(path, states);
+    }
+
+    // Remove every entry, and reset the statistics.
+    pub(super) fn clear(&self) {
+        for shard in self.shards.iter() {
+            let mut clock = shard.clock.write().expect("valid lock");
+            clock.index.clear();
+            clock.slots.clear();
+            clock.hand = 0;
+            shard.hits.store(0, Ordering::Relaxed);
+            shard.misses.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub(super) fn stats(&self) -> CacheStats {
+        let mut stats = CacheStats::default();
+        for shard in self.shards.iter() {
+            let clock = shard.clock.read().expect("valid lock");
+            stats.hits += shard.hits.load(Ordering::Relaxed);
+            stats.misses += shard.misses.load(Ordering::Relaxed);
+            stats.len += clock.slots.len();
+            stats.capacity += clock.capacity;
+        }
+        stats
+    }
+}
+
+impl Clock {
+    fn insert(&mut self, path: String, states: Vec<StateId>) {
+        if let Some(&slot_idx) = self.index.get(&path) {
+            self.slots[slot_idx].states = states;
+            return;
+        }
+        if self.capacity == 0 {
+            return;
+        }
+
+        let slot = Slot {
+            path: path.clone(),
+            states,
+            referenced: AtomicBool::new(false),
+        };
+        if self.slots.len() < self.capacity {
+            self.index.insert(path, self.slots.len());
+            self.slots.push(slot);
+            return;
+        }
+
+        // Give referenced entries a second chance. This terminates within one
+        // full sweep, as every bit passed over is cleared.
+        while self.slots[self.hand]
+            .referenced
+            .swap(false, Ordering::Relaxed)
+        {
+            self.hand = (self.hand + 1) % self.slots.len();
+        }
+        let evicted = std::mem::replace(&mut self.slots[self.hand], slot);
+        self.index.remove(&evicted.path);
+        self.index.insert(path, self.hand);
+        self.hand = (self.hand + 1) % self.slots.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn states(id: u32) -> Vec<StateId> {
+        vec![StateId(id)]
+    }
+
+    #[test]
+    fn test_clock_eviction() {
+        let mut clock = Clock {
+            capacity: 2,
+            index: HashMap::new(),
+            slots: Vec::new(),
+            hand: 0,
+        };
+        clock.insert("a".to_string(), states(1));
+        clock.insert("b".to_string(), states(2));
+
+        // "a" was read since it was inserted, so "b" is evicted to make room
+        clock.slots[clock.index["a"]]
+            .referenced
+            .store(true, Ordering::Relaxed);
+        clock.insert("c".to_string(), states(3));
+        assert!(!clock.index.contains_key("b"));
+        assert_eq!(clock.slots[clock.index["a"]].states, states(1));
+        assert_eq!(clock.slots[clock.index["c"]].states, states(3));
+
+        // Now neither entry is referenced, so the hand evicts "a" next
+        clock.insert("d".to_string(), states(4));
+        assert!(!clock.index.contains_key("a"));
+        assert_eq!(clock.slots.len(), 2);
+    }
+
+    #[test]
+    fn test_shard_count() {
+        assert_eq!(TransitionCache::new(0).shards.len(), 1);
+        assert_eq!(TransitionCache::new(100).shards.len(), 1);
+        assert_eq!(TransitionCache::new(1000).shards.len(), 15);
+        assert_eq!(TransitionCache::new(65_536).shards.len(), 64);
+    }
+
+    #[test]
+    fn test_sharded_cache() {
+        let cache = TransitionCache::with_shards(100, 8);
+        for i in 0..1000 {
+            cache.insert(i.to_string(), states(i));
+        }
+        // The most recent insert is never evicted, whichever shard it's in
+        assert_eq!(cache.get("999"), Some(states(999)));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 0));
+        assert_eq!(stats.capacity, 100);
+        assert!(stats.len <= 100);
+
+        cache.clear();
+        assert_eq!(cache.get("999"), None);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 0,
+                misses: 1,
+                len: 0,
+                capacity: 100
+            }
+        );
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let cache = TransitionCache::new(0);
+        cache.insert("a".to_string(), states(1));
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.stats().len, 0);
+    }
+}
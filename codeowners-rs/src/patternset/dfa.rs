@@ -0,0 +1,419 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use super::{
+    nfa::{Nfa, StateId, Transition},
+    TransitionKind,
+};
+
+// A deterministic finite automaton (DFA) built from the NFA by subset
+// construction. Each DFA state corresponds to the set of NFA states the NFA
+// would be in after matching some path prefix, so matching a path takes a
+// single step per component.
+//
+// As path components are strings rather than characters, edges can't be
+// enumerated the usual way. Instead, each DFA state has a hash map for its
+// literal edges, and a bitmask over its wildcard edges: when a component isn't
+// one of the literals, the wildcard conditions are tested, and the set of
+// conditions that matched selects the next state. Wildcard combinations are
+// enumerated up front, skipping those that can't match the same component.
+//
+// Most patterns end in a state that loops back to itself on any component, so
+// once entered, the pattern matches whatever follows. Keeping these states in
+// the DFA's state sets would create a DFA state for every combination of
+// patterns matched along the way, so instead they're left out, and the edges
+// entering them record the patterns they match as outputs.
+//
+// Construction gives up if the DFA would be too large, in which case the
+// matcher keeps using the NFA.
+#[derive(Clone)]
+pub(crate) struct Dfa {
+    states: Vec<DfaState>,
+    start: Edge,
+    // Sets of patterns matched by taking an edge. The first set is empty.
+    outputs: Vec<Vec<usize>>,
+}
+
+#[derive(Clone)]
+struct DfaState {
+    // The patterns matched by paths ending in this state, not including those
+    // matched by edge outputs.
+    terminal_for_patterns: Vec<usize>,
+    // Literal edges. These take precedence over the wildcard edges, as they
+    // already account for any wildcards matching the literal.
+    literals: HashMap<String, Edge>,
+    // The wildcard transitions leaving the NFA states in this state, excluding
+    // unconditional ones, which always match.
+    wildcards: Vec<Transition>,
+    // The edge for each combination of matching wildcards, keyed by a bitmask
+    // of indices into `wildcards`.
+    wildcard_edges: HashMap<u64, Edge>,
+    // True if no more patterns can match from this state.
+    dead: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    target: u32,
+    output: u32,
+}
+
+impl Dfa {
+    // The maximum number of wildcard transitions leaving a single DFA state,
+    // which is limited by the size of the bitmask.
+    const MAX_WILDCARDS: usize = 64;
+
+    // The maximum number of wildcard combinations enumerated for a single DFA
+    // state.
+    const MAX_WILDCARD_COMBINATIONS: usize = 1024;
+
+    pub(crate) fn build(nfa: &Nfa, max_states: usize) -> Option<Dfa> {
+        Builder {
+            nfa,
+            max_states,
+            states: Vec::new(),
+            outputs: vec![Vec::new()],
+            index: HashMap::new(),
+            output_index: HashMap::from([(Vec::new(), 0)]),
+            queue: Vec::new(),
+        }
+        .build()
+    }
+
+    pub(crate) fn matching_patterns(&self, path_segments: &[Cow<str>]) -> Vec<usize> {
+        let mut matches = self.outputs[self.start.output as usize].clone();
+        let mut state = &self.states[self.start.target as usize];
+        for segment in path_segments {
+            if state.dead {
+                break;
+            }
+            let edge = state.next_edge(segment);
+            matches.extend(&self.outputs[edge.output as usize]);
+            state = &self.states[edge.target as usize];
+        }
+        matches.extend(&state.terminal_for_patterns);
+        matches.sort();
+        matches.dedup();
+        matches
+    }
+}
+
+impl DfaState {
+    fn next_edge(&self, segment: &str) -> Edge {
+        if let Some(&edge) = self.literals.get(segment) {
+            return edge;
+        }
+        let mask = self
+            .wildcards
+            .iter()
+            .enumerate()
+            .filter(|(_, transition)| transition.is_match(segment))
+            .fold(0, |mask, (idx, _)| mask | 1 << idx);
+        // The wildcards matching a component all overlap with each other, and
+        // `wildcard_combinations` enumerates every set of pairwise overlapping
+        // wildcards, so there's always an edge for the mask.
+        self.wildcard_edges[&mask]
+    }
+}
+
+struct Builder<'a> {
+    nfa: &'a Nfa,
+    max_states: usize,
+    states: Vec<DfaState>,
+    outputs: Vec<Vec<usize>>,
+    // Maps each set of NFA states (sorted) to its DFA state.
+    index: HashMap<Vec<StateId>, u32>,
+    output_index: HashMap<Vec<usize>, u32>,
+    // DFA states whose edges haven't been built yet.
+    queue: Vec<(u32, Vec<StateId>)>,
+}
+
+impl<'a> Builder<'a> {
+    fn build(mut self) -> Option<Dfa> {
+        let start = self.edge_to(self.nfa.initial_states())?;
+        while let Some((state_id, nfa_states)) = self.queue.pop() {
+            self.build_edges(state_id, &nfa_states)?;
+        }
+        Some(Dfa {
+            states: self.states,
+            start,
+            outputs: self.outputs,
+        })
+    }
+
+    // Return an edge to the DFA state for a set of NFA states (not including
+    // the states reached by epsilon transitions), adding the state if it
+    // doesn't exist yet.
+    fn edge_to(&mut self, mut nfa_states: Vec<StateId>) -> Option<Edge> {
+        // The matcher only follows epsilon transitions from the states entered
+        // by regular transitions, and the builder never chains them
+        let epsilon_states = nfa_states
+            .iter()
+            .filter_map(|&id| self.nfa.epsilon_transitions_from(id))
+            .collect::<Vec<_>>();
+        nfa_states.extend(epsilon_states);
+
+        // Absorbing states become outputs, and states with no way forward that
+        // don't match anything are dropped
+        let mut output = Vec::new();
+        nfa_states.retain(|&id| {
+            let state = self.nfa.state(id);
+            let terminal_for_patterns = state.terminal_for_patterns.iter().flatten();
+            if self.is_absorbing(id) {
+                output.extend(terminal_for_patterns);
+                false
+            } else {
+                state.has_transitions() || state.terminal_for_patterns.is_some()
+            }
+        });
+        nfa_states.sort_by_key(|&id| id.0);
+        nfa_states.dedup();
+        output.sort();
+        output.dedup();
+
+        let target = match self.index.get(&nfa_states) {
+            Some(&state_id) => state_id,
+            None => self.add_state(nfa_states)?,
+        };
+        let output = match self.output_index.get(&output) {
+            Some(&output_id) => output_id,
+            None => {
+                self.outputs.push(output.clone());
+                self.output_index
+                    .insert(output, self.outputs.len() as u32 - 1);
+                self.outputs.len() as u32 - 1
+            }
+        };
+        Some(Edge { target, output })
+    }
+
+    fn add_state(&mut self, nfa_states: Vec<StateId>) -> Option<u32> {
+        if self.states.len() >= self.max_states {
+            return None;
+        }
+
+        let mut terminal_for_patterns = nfa_states
+            .iter()
+            .flat_map(|&id| self.nfa.state(id).terminal_for_patterns.iter().flatten())
+            .copied()
+            .collect::<Vec<_>>();
+        terminal_for_patterns.sort();
+        terminal_for_patterns.dedup();
+
+        let state_id = self.states.len() as u32;
+        self.states.push(DfaState {
+            terminal_for_patterns,
+            literals: HashMap::new(),
+            wildcards: Vec::new(),
+            wildcard_edges: HashMap::new(),
+            dead: nfa_states.is_empty(),
+        });
+        self.index.insert(nfa_states.clone(), state_id);
+        self.queue.push((state_id, nfa_states));
+        Some(state_id)
+    }
+
+    // Returns true if the state can never be left, and stays active whatever
+    // path components follow: its only transition is a wildcard back to itself.
+    fn is_absorbing(&self, id: StateId) -> bool {
+        let state = self.nfa.state(id);
+        let mut targets = state.targets();
+        let mut wildcards = state.wildcard_transitions();
+        state.epsilon_transition.is_none()
+            && targets.next() == Some(id)
+            && targets.next().is_none()
+            && wildcards
+                .next()
+                .is_some_and(|t| t.kind() == TransitionKind::Unconditional)
+    }
+
+    fn build_edges(&mut self, state_id: u32, nfa_states: &[StateId]) -> Option<()> {
+        let mut literals: HashMap<&str, Vec<StateId>> = HashMap::new();
+        let mut wildcards = Vec::new();
+        let mut unconditional = Vec::new();
+        for &id in nfa_states {
+            let state = self.nfa.state(id);
+            for (segment, target) in state.literal_transitions() {
+                literals.entry(segment).or_default().push(target);
+            }
+            for transition in state.wildcard_transitions() {
+                match transition.kind() {
+                    TransitionKind::Unconditional => unconditional.push(transition.target),
+                    _ => wildcards.push(transition),
+                }
+            }
+        }
+        if wildcards.len() > Dfa::MAX_WILDCARDS {
+            return None;
+        }
+
+        let mut literal_edges = HashMap::new();
+        for (segment, mut targets) in literals {
+            targets.extend(&unconditional);
+            targets.extend(
+                wildcards
+                    .iter()
+                    .filter(|t| t.is_match(segment))
+                    .map(|t| t.target),
+            );
+            literal_edges.insert(segment.to_owned(), self.edge_to(targets)?);
+        }
+
+        let mut wildcard_edges = HashMap::new();
+        for mask in wildcard_combinations(&wildcards)? {
+            let mut targets = unconditional.clone();
+            targets.extend(
+                wildcards
+                    .iter()
+                    .enumerate()
+                    .filter(|(idx, _)| mask & 1 << idx != 0)
+                    .map(|(_, t)| t.target),
+            );
+            wildcard_edges.insert(mask, self.edge_to(targets)?);
+        }
+
+        let state = &mut self.states[state_id as usize];
+        state.literals = literal_edges;
+        state.wildcards = wildcards.into_iter().cloned().collect();
+        state.wildcard_edges = wildcard_edges;
+        Some(())
+    }
+}
+
+// Return a bitmask for every combination of wildcards that could match the
+// same path component. As `is_disjoint_from` is conservative, this may include
+// combinations that can't occur, but never misses one that can.
+fn wildcard_combinations(wildcards: &[&Transition]) -> Option<Vec<u64>> {
+    // For each wildcard, a bitmask of the other wildcards it may overlap with
+    let overlaps = wildcards
+        .iter()
+        .map(|a| {
+            wildcards
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| !a.is_disjoint_from(b))
+                .fold(0u64, |mask, (idx, _)| mask | 1 << idx)
+        })
+        .collect::<Vec<_>>();
+
+    let mut combinations = vec![0];
+    let mut stack = vec![(0, 0u64)];
+    while let Some((start, mask)) = stack.pop() {
+        for (idx, overlap) in overlaps.iter().enumerate().skip(start) {
+            if overlap & mask != mask {
+                continue;
+            }
+            if combinations.len() >= Dfa::MAX_WILDCARD_COMBINATIONS {
+                return None;
+            }
+            let mask = mask | 1 << idx;
+            combinations.push(mask);
+            stack.push((idx + 1, mask));
+        }
+    }
+    Some(combinations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patternset::{Builder, Matcher};
+
+    fn matcher_for_patterns(patterns: &[&str]) -> Matcher {
+        let mut builder = Builder::new();
+        for pattern in patterns {
+            builder.add(pattern);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_dfa_matches_nfa() {
+        let patterns = [
+            "*",
+            "*.rs",
+            "*.md",
+            "/src/",
+            "/src/**/*.rs",
+            "/src/*/mod.rs",
+            "docs/",
+            "docs/*",
+            "**/test/**",
+            "foo-*",
+            "*-bar",
+            "*oo*",
+            "b?z",
+            "a\\*b",
+            "/x/**",
+            "/x/**/y/**/z",
+        ];
+        let paths = [
+            "",
+            "README.md",
+            "main.rs",
+            "src/main.rs",
+            "src/parser/mod.rs",
+            "src/parser/deep/lexer.rs",
+            "src/docs/README.md",
+            "docs/index.md",
+            "docs/guide/intro.txt",
+            "a/test/b.txt",
+            "test/b.rs",
+            "foo-bar",
+            "x/foo-baz/bar",
+            "baz",
+            "a/b/bz",
+            "a*b",
+            "axb",
+            "x/y/z",
+            "x/a/y/b/c/z",
+            "x/z",
+        ];
+
+        // Try each suffix of the pattern list, so states combine differently
+        for i in 0..patterns.len() {
+            let nfa_matcher = matcher_for_patterns(&patterns[i..]);
+            let mut dfa_matcher = nfa_matcher.clone();
+            assert!(dfa_matcher.determinize(10_000));
+            for path in paths {
+                let mut expected = nfa_matcher.matching_patterns(path);
+                expected.sort();
+                expected.dedup();
+                assert_eq!(
+                    dfa_matcher.matching_patterns(path),
+                    expected,
+                    "mismatch for {:?} with patterns {:?}",
+                    path,
+                    &patterns[i..]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_state_limit() {
+        let patterns = (0..10).map(|i| format!("/dir-{}/", i)).collect::<Vec<_>>();
+        let patterns = patterns.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+        let mut matcher = matcher_for_patterns(&patterns);
+        assert!(!matcher.determinize(5));
+        assert!(!matcher.is_determinized());
+        assert_eq!(matcher.matching_patterns("dir-3/a"), vec![3]);
+
+        assert!(matcher.determinize(100));
+        assert!(matcher.is_determinized());
+        assert_eq!(matcher.matching_patterns("dir-3/a"), vec![3]);
+    }
+
+    #[test]
+    fn test_wildcard_combinations() {
+        let transitions = ["*.rs", "*.md", "foo*", "*bar*"]
+            .iter()
+            .map(|s| Transition::new(s.to_string(), StateId(0)))
+            .collect::<Vec<_>>();
+        let transitions = transitions.iter().collect::<Vec<_>>();
+
+        // Suffixes are disjoint from each other, but nothing else is known to be
+        let mut combinations = wildcard_combinations(&transitions).unwrap();
+        combinations.sort();
+        assert_eq!(combinations, vec![0, 1, 2, 4, 5, 6, 8, 9, 10, 12, 13, 14]);
+    }
+}
@@ -1,28 +1,82 @@
-use std::{
-    borrow::Cow,
-    collections::HashMap,
-    path::Path,
-    sync::{Arc, RwLock},
+use std::{borrow::Cow, path::Path, sync::Arc};
+
+use super::{
+    cache::{CacheStats, TransitionCache},
+    dfa::Dfa,
+    nfa::Nfa,
+    nfa::StateId,
+    MatchTrace,
 };
 
-use super::{nfa::Nfa, nfa::StateId};
-
 /// Matches a path against a set of patterns. Includes a thread-safe transition
 /// cache to speed up subsequent lookups. Created using a [`super::Builder`].
+///
+/// The cache maps directory prefixes to the NFA states reached after matching
+/// them, and holds at most [`Matcher::DEFAULT_CACHE_CAPACITY`] entries unless
+/// changed with [`set_cache_capacity`](fn@Matcher::set_cache_capacity). Clones
+/// of a `Matcher` share its cache.
 #[derive(Clone)]
 pub struct Matcher {
     nfa: Nfa,
-    transition_cache: Arc<RwLock<HashMap<String, Vec<StateId>>>>,
+    transition_cache: Arc<TransitionCache>,
+    dfa: Option<Arc<Dfa>>,
 }
 
 impl Matcher {
+    /// The default maximum number of entries in the transition cache.
+    pub const DEFAULT_CACHE_CAPACITY: usize = 65_536;
+
     pub(crate) fn new(nfa: Nfa) -> Matcher {
         Self {
             nfa,
-            transition_cache: Arc::new(RwLock::new(HashMap::new())),
+            transition_cache: Arc::new(TransitionCache::new(Self::DEFAULT_CACHE_CAPACITY)),
+            dfa: None,
         }
     }
 
+    /// Compile the patterns into a deterministic automaton, which takes a
+    /// single step per path component rather than testing every pattern that
+    /// could apply. This speeds up matching for large sets of patterns, at the
+    /// cost of a one-off compilation step and more memory. The transition
+    /// cache isn't used once the matcher is determinized.
+    ///
+    /// Some sets of patterns (particularly those with many overlapping
+    /// wildcards) would produce very large automata. If the automaton would
+    /// have more than `max_states` states, compilation is abandoned and the
+    /// matcher continues to work as before. Returns whether compilation
+    /// succeeded.
+    pub fn determinize(&mut self, max_states: usize) -> bool {
+        self.dfa = Dfa::build(&self.nfa, max_states).map(Arc::new);
+        self.dfa.is_some()
+    }
+
+    /// Returns true if the matcher has been compiled into a deterministic
+    /// automaton by [`determinize`](fn@Matcher::determinize).
+    pub fn is_determinized(&self) -> bool {
+        self.dfa.is_some()
+    }
+
+    /// Replace the transition cache with an empty one that holds at most
+    /// `capacity` entries. When the cache is full, the least recently used
+    /// entries are evicted first (approximately). A capacity of zero disables
+    /// caching. Existing clones of the matcher keep using the old cache.
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.transition_cache = Arc::new(TransitionCache::new(capacity));
+    }
+
+    /// Remove every entry from the transition cache, and reset its statistics.
+    /// This affects every clone of the matcher that shares the cache.
+    pub fn clear_cache(&self) {
+        self.transition_cache.clear();
+    }
+
+    /// Returns the transition cache's hit and miss counts, and its current and
+    /// maximum number of entries. Counts are shared by every clone of the
+    /// matcher that shares the cache.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.transition_cache.stats()
+    }
+
     /// Match a path against the patterns in the set. Returns a list of pattern
     /// indices that match the path. The pattern indices match the order in which
     /// the patterns were added to the builder.
@@ -32,6 +86,9 @@ impl Matcher {
             .iter()
             .map(|c| c.to_string_lossy())
             .collect::<Vec<_>>();
+        if let Some(dfa) = &self.dfa {
+            return dfa.matching_patterns(&components);
+        }
         let initial_states = self.nfa.initial_states();
         let final_states = self.next_states(&components, initial_states);
 
@@ -79,9 +136,8 @@ impl Matcher {
         let mut next_states = Vec::new();
         for state_id in states {
             self.nfa
-                .transitions_from(state_id)
-                .filter(|transition| transition.is_match(segment))
-                .for_each(|transition| next_states.push(transition.target));
+                .state(state_id)
+                .matching_targets(segment, &mut next_states);
         }
 
         // Automatically traverse epsilon edges
@@ -93,19 +149,39 @@ impl Matcher {
         next_states
     }
 
+    /// Match a path against the patterns in the set, like
+    /// [`matching_patterns`](fn@Matcher::matching_patterns), but also record
+    /// how each pattern matched: the NFA states passed through and the
+    /// transition taken for each path component. Returns one trace per
+    /// matching pattern, ordered by pattern index. This is much slower than
+    /// `matching_patterns`, and is intended for explaining matches to users.
+    pub fn trace_matching_patterns(&self, path: impl AsRef<Path>) -> Vec<MatchTrace> {
+        super::trace::trace_matching_patterns(&self.nfa, path.as_ref())
+    }
+
+    /// Find patterns that are shadowed by other patterns, meaning that every
+    /// path the pattern matches is also matched by one or more of the patterns
+    /// that are allowed to shadow it. `can_shadow(a, b)` should return true if
+    /// pattern `b` takes precedence over pattern `a`. This works on the
+    /// patterns alone, without needing a list of paths.
+    ///
+    /// Returns a list of (shadowed pattern, shadowing patterns) pairs. The
+    /// analysis is conservative: it won't report patterns that aren't
+    /// shadowed, but may miss some that are (for instance, those involving
+    /// complex wildcards).
+    pub fn shadowed_patterns(
+        &self,
+        can_shadow: impl Fn(usize, usize) -> bool,
+    ) -> Vec<(usize, Vec<usize>)> {
+        super::shadow::shadowed_patterns(&self.nfa, can_shadow)
+    }
+
     fn get_cached_states_for(&self, path: &str) -> Option<Vec<StateId>> {
-        self.transition_cache
-            .read()
-            .expect("valid lock")
-            .get(path)
-            .cloned()
+        self.transition_cache.get(path)
     }
 
     fn set_cached_states_for(&self, path: String, states: Vec<StateId>) {
-        self.transition_cache
-            .write()
-            .expect("valid lock")
-            .insert(path, states);
+        self.transition_cache.insert(path, states);
     }
 }
 
@@ -113,7 +189,7 @@ impl Matcher {
 mod tests {
     use std::collections::HashSet;
 
-    use crate::patternset::Builder;
+    use crate::patternset::{Builder, TransitionKind};
 
     use super::*;
 
@@ -249,6 +325,192 @@ mod tests {
         assert_matches(&matcher, "a", &patterns, &[]);
     }
 
+    #[test]
+    fn test_wide_fan_out() {
+        // Literal, prefix and suffix transitions from the same states are
+        // indexed separately, so make sure they still combine correctly
+        let mut patterns = (0..100).map(|i| format!("/dir-{}/", i)).collect::<Vec<_>>();
+        patterns.extend((0..100).map(|i| format!("*.ext{}", i)));
+        patterns.extend(["/dir-*/", "/dir-1*/", "*1", "é*", "*é", "*.ext1"].map(String::from));
+        let patterns = patterns.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+        let matcher = matcher_for_patterns(&patterns);
+
+        assert_matches(&matcher, "dir-1/a", &patterns, &[1, 200, 201, 202]);
+        assert_matches(&matcher, "dir-10/a", &patterns, &[10, 200, 201]);
+        assert_matches(&matcher, "dir-2/a", &patterns, &[2, 200]);
+        assert_matches(&matcher, "dir-x/a.ext1", &patterns, &[101, 200, 202, 205]);
+        assert_matches(&matcher, "a.ext11", &patterns, &[111, 202]);
+        assert_matches(&matcher, "éa", &patterns, &[203]);
+        assert_matches(&matcher, "aé", &patterns, &[204]);
+        assert_matches(&matcher, "é", &patterns, &[203, 204]);
+        assert_matches(&matcher, "ü", &patterns, &[]);
+    }
+
+    #[test]
+    fn test_combined_wildcards() {
+        // Enough contains and regex patterns leave the same states for them to
+        // be tested together
+        let patterns = [
+            "*foo*",
+            "*bar*",
+            "b?z",
+            "q*x*y",
+            "*",
+            "*.rs",
+            "/src/*oo*",
+            "/src/b*r?",
+            "/src/*a*",
+            "/src/*/mod.rs",
+            "/src/[x",
+            "/src/z*z",
+            "*ob*",
+        ];
+        let matcher = matcher_for_patterns(&patterns);
+
+        // Overlapping and repeated fragments only match once
+        assert_matches(&matcher, "afoob", &patterns, &[0, 4, 12]);
+        assert_matches(&matcher, "foofoo", &patterns, &[0, 4]);
+        assert_matches(&matcher, "foobar", &patterns, &[0, 1, 4, 12]);
+        assert_matches(&matcher, "biz", &patterns, &[2, 4]);
+        assert_matches(&matcher, "qaxby", &patterns, &[3, 4]);
+        assert_matches(&matcher, "qaxb", &patterns, &[4]);
+        assert_matches(&matcher, "src/boo", &patterns, &[4, 6]);
+        assert_matches(&matcher, "src/bars", &patterns, &[1, 4, 7, 8]);
+        assert_matches(&matcher, "src/[x", &patterns, &[4, 10]);
+        assert_matches(&matcher, "src/a/mod.rs", &patterns, &[4, 5, 8, 9]);
+        assert_matches(&matcher, "src/zaz", &patterns, &[4, 8, 11]);
+    }
+
+    #[test]
+    fn test_shadowed_patterns() {
+        let patterns = [
+            "/docs/api/",
+            "/docs/",
+            "*.rs",
+            "src/*.rs",
+            "/src/",
+            "*.rs",
+            "/build/**/*.log",
+            "/build/*.log",
+            "/lib/*_test.go",
+            "*.go",
+            "/vendor/a.txt",
+            "a.txt",
+            "/vendor/b/",
+            "/vendor/b/*",
+            "/bin/",
+            "/bin/tool",
+            "foo*",
+            "foobar*",
+            "*bar",
+        ];
+        let matcher = matcher_for_patterns(&patterns);
+        let shadowed = matcher.shadowed_patterns(|a, b| b > a);
+        assert_eq!(
+            shadowed,
+            vec![
+                (0, vec![1]),
+                (2, vec![5]),
+                (3, vec![4, 5]),
+                (8, vec![9]),
+                (10, vec![11]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trace_matching_patterns() {
+        let patterns = ["/src/**/*.rs", "src/", "*_test.rs", "/src/p?rser/*"];
+        let matcher = matcher_for_patterns(&patterns);
+
+        // Traces are produced for exactly the matching patterns
+        for path in [
+            "src/parser/mod.rs",
+            "src/a/b/c_test.rs",
+            "lib/x_test.rs",
+            "foo",
+        ] {
+            let traced = matcher
+                .trace_matching_patterns(path)
+                .into_iter()
+                .map(|trace| trace.pattern)
+                .collect::<HashSet<_>>();
+            assert_eq!(traced, HashSet::from_iter(matcher.matching_patterns(path)));
+        }
+
+        let traces = matcher.trace_matching_patterns("src/parser/mod.rs");
+        let steps = |pattern: usize| {
+            let trace = traces.iter().find(|t| t.pattern == pattern).unwrap();
+            trace
+                .steps
+                .iter()
+                .map(|step| (step.component, step.segment.as_str(), step.kind))
+                .collect::<Vec<_>>()
+        };
+        use TransitionKind::*;
+        assert_eq!(
+            steps(0),
+            vec![
+                (Some(0), "src", Literal),
+                (None, "**", Epsilon),
+                (Some(1), "**", Unconditional),
+                (Some(2), "*.rs", Suffix),
+                (None, "**", Epsilon),
+            ]
+        );
+        assert_eq!(
+            steps(1),
+            vec![
+                (None, "**", Epsilon),
+                (Some(0), "src", Literal),
+                (Some(1), "*", Unconditional),
+                (None, "**", Epsilon),
+                (Some(2), "**", Unconditional),
+            ]
+        );
+        assert_eq!(
+            steps(3),
+            vec![
+                (Some(0), "src", Literal),
+                (Some(1), "p?rser", Regex),
+                (Some(2), "*", Unconditional),
+            ]
+        );
+
+        // Each step starts where the previous one ended
+        for trace in &traces {
+            for pair in trace.steps.windows(2) {
+                assert_eq!(pair[0].to, pair[1].from);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bounded_cache() {
+        let patterns = ["/src/**/*.rs", "docs/", "*.md"];
+        let mut matcher = matcher_for_patterns(&patterns);
+        matcher.set_cache_capacity(2);
+
+        // Evicted prefixes are recomputed, so results don't depend on the cache
+        for _ in 0..2 {
+            assert_matches(&matcher, "src/a/b/main.rs", &patterns, &[0]);
+            assert_matches(&matcher, "docs/guide/intro.md", &patterns, &[1, 2]);
+            assert_matches(&matcher, "README.md", &patterns, &[2]);
+        }
+        let stats = matcher.cache_stats();
+        assert_eq!((stats.len, stats.capacity), (2, 2));
+        assert!(stats.hits > 0 && stats.misses > 0);
+
+        matcher.clear_cache();
+        assert_eq!(
+            matcher.cache_stats(),
+            CacheStats {
+                capacity: 2,
+                ..Default::default()
+            }
+        );
+    }
+
     fn assert_matches(matcher: &Matcher, path: &str, patterns: &[&str], expected: &[usize]) {
         assert_eq!(
             HashSet::<usize>::from_iter(matcher.matching_patterns(path).into_iter()),
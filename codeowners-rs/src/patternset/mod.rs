@@ -1,6 +1,12 @@
 mod builder;
+mod cache;
+mod dfa;
 mod matcher;
 mod nfa;
+mod shadow;
+mod trace;
 
 pub use self::builder::Builder;
+pub use self::cache::CacheStats;
 pub use self::matcher::Matcher;
+pub use self::trace::{MatchTrace, TraceStep, TransitionKind};
@@ -1,3 +1,7 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use super::trace::TransitionKind;
+
 // Newtype for a state index in the NFA.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct StateId(pub u32);
@@ -13,8 +17,19 @@ impl From<StateId> for usize {
 pub(crate) struct State {
     // Denotes this state as a terminal state for all patterns in the vector.
     pub(crate) terminal_for_patterns: Option<Vec<usize>>,
-    // Transitions from this state to other states.
-    pub(crate) transitions: Vec<Transition>,
+    // Literal transitions, keyed by path segment. States near the root can have
+    // thousands of these, so they're looked up rather than tested one by one.
+    literal_transitions: HashMap<String, StateId>,
+    // Prefix (`foo-*`) and suffix (`*.rs`) transitions, indexed so they can be
+    // matched without testing each one.
+    affix_transitions: AffixTransitions,
+    // All other transitions, which are tested one by one unless they're
+    // combined below.
+    other_transitions: Vec<Transition>,
+    // The contains and regex transitions in `other_transitions`, combined so
+    // they can be tested together. Set by `Nfa::compile` for states with
+    // enough of them to make it worthwhile.
+    combined_transitions: Option<CombinedTransitions>,
     // Epislon transitions are unconditionally traversed when _entering_ this
     // state. They're used for handling recursive (**) patterns. Note they
     // differ from wildcard transitions, which match any segment, but are
@@ -27,13 +42,26 @@ impl State {
     pub(crate) fn new() -> Self {
         Self {
             terminal_for_patterns: None,
-            transitions: Vec::new(),
+            literal_transitions: HashMap::new(),
+            affix_transitions: AffixTransitions::default(),
+            other_transitions: Vec::new(),
+            combined_transitions: None,
             epsilon_transition: None,
         }
     }
 
     pub(crate) fn add_transition(&mut self, transition: Transition) {
-        self.transitions.push(transition);
+        match transition.condition {
+            TransitionCondition::Literal => {
+                self.literal_transitions
+                    .insert(transition.path_segment, transition.target);
+            }
+            TransitionCondition::Prefix | TransitionCondition::Suffix => {
+                self.affix_transitions.add(transition)
+            }
+            _ => self.other_transitions.push(transition),
+        }
+        self.combined_transitions = None;
     }
 
     pub(crate) fn mark_as_terminal(&mut self, pattern_id: usize) {
@@ -43,6 +71,235 @@ impl State {
             self.terminal_for_patterns = Some(vec![pattern_id]);
         }
     }
+
+    // Add the targets of all transitions matching the path segment to `targets`.
+    pub(crate) fn matching_targets(&self, segment: &str, targets: &mut Vec<StateId>) {
+        if let Some(&target) = self.literal_transitions.get(segment) {
+            targets.push(target);
+        }
+        self.affix_transitions.matching_targets(segment, targets);
+        match &self.combined_transitions {
+            Some(combined) => {
+                combined.matching_targets(segment, targets);
+                targets.extend(
+                    self.other_transitions
+                        .iter()
+                        .filter(|t| matches!(t.condition, TransitionCondition::Unconditional))
+                        .map(|t| t.target),
+                );
+            }
+            None => targets.extend(
+                self.other_transitions
+                    .iter()
+                    .filter(|t| t.is_match(segment))
+                    .map(|t| t.target),
+            ),
+        }
+    }
+
+    // Combine the contains and regex transitions into a `CombinedTransitions`
+    // if there are enough of them.
+    fn combine_transitions(&mut self) {
+        let transitions = self
+            .other_transitions
+            .iter()
+            .filter(|t| !matches!(t.condition, TransitionCondition::Unconditional))
+            .collect::<Vec<_>>();
+        if transitions.len() >= CombinedTransitions::MIN_TRANSITIONS {
+            self.combined_transitions = CombinedTransitions::new(&transitions);
+        }
+    }
+
+    pub(crate) fn has_transitions(&self) -> bool {
+        !self.literal_transitions.is_empty()
+            || !self.affix_transitions.transitions.is_empty()
+            || !self.other_transitions.is_empty()
+    }
+
+    // The target of the literal transition for the path segment, if any.
+    pub(crate) fn literal_target(&self, segment: &str) -> Option<StateId> {
+        self.literal_transitions.get(segment).copied()
+    }
+
+    // The targets of all transitions.
+    pub(crate) fn targets(&self) -> impl Iterator<Item = StateId> + '_ {
+        self.literal_transitions
+            .values()
+            .copied()
+            .chain(self.wildcard_transitions().map(|t| t.target))
+    }
+
+    // The literal transitions, as (path segment, target) pairs.
+    pub(crate) fn literal_transitions(&self) -> impl Iterator<Item = (&str, StateId)> {
+        self.literal_transitions
+            .iter()
+            .map(|(segment, &target)| (segment.as_str(), target))
+    }
+
+    // The transitions that aren't literals.
+    pub(crate) fn wildcard_transitions(&self) -> impl Iterator<Item = &Transition> {
+        self.other_transitions
+            .iter()
+            .chain(&self.affix_transitions.transitions)
+    }
+
+    // All transitions, wildcards first. Literal transitions aren't stored as
+    // `Transition`s, so they're created on the fly.
+    pub(crate) fn transitions(&self) -> impl Iterator<Item = Cow<'_, Transition>> {
+        self.wildcard_transitions().map(Cow::Borrowed).chain(
+            self.literal_transitions()
+                .map(|(segment, target)| Cow::Owned(Transition::literal(segment, target))),
+        )
+    }
+
+    // The targets of the transitions that match every path segment `class`
+    // matches. Like `Transition::is_subset_of`, this is conservative.
+    pub(crate) fn targets_covering<'a>(
+        &'a self,
+        class: &'a Transition,
+    ) -> impl Iterator<Item = StateId> + 'a {
+        // Literal transitions only cover literal classes with the same segment
+        let literal_target = match class.condition {
+            TransitionCondition::Literal => self.literal_transitions.get(&class.path_segment),
+            _ => None,
+        };
+        literal_target.copied().into_iter().chain(
+            self.wildcard_transitions()
+                .filter(|t| class.is_subset_of(t))
+                .map(|t| t.target),
+        )
+    }
+}
+
+// Prefix and suffix transitions, with their targets indexed by the literal
+// part of the segment (the fragment). To match a path segment, we only need to
+// look up the segment's prefixes and suffixes with the same lengths as the
+// fragments, so a state with hundreds of `*.ext` transitions is matched with a
+// handful of lookups.
+#[derive(Debug, Clone, Default)]
+struct AffixTransitions {
+    transitions: Vec<Transition>,
+    prefixes: HashMap<String, Vec<StateId>>,
+    suffixes: HashMap<String, Vec<StateId>>,
+    // The distinct fragment lengths (in bytes) in each of the maps.
+    prefix_lens: Vec<usize>,
+    suffix_lens: Vec<usize>,
+}
+
+impl AffixTransitions {
+    fn add(&mut self, transition: Transition) {
+        let fragment = transition.fragment().to_owned();
+        let (index, lens) = match transition.condition {
+            TransitionCondition::Prefix => (&mut self.prefixes, &mut self.prefix_lens),
+            _ => (&mut self.suffixes, &mut self.suffix_lens),
+        };
+        if !lens.contains(&fragment.len()) {
+            lens.push(fragment.len());
+        }
+        index.entry(fragment).or_default().push(transition.target);
+        self.transitions.push(transition);
+    }
+
+    fn matching_targets(&self, segment: &str, targets: &mut Vec<StateId>) {
+        for &len in &self.prefix_lens {
+            if let Some(prefix) = segment.get(..len) {
+                targets.extend(self.prefixes.get(prefix).into_iter().flatten());
+            }
+        }
+        for &len in &self.suffix_lens {
+            let suffix = segment
+                .len()
+                .checked_sub(len)
+                .and_then(|start| segment.get(start..));
+            if let Some(suffix) = suffix {
+                targets.extend(self.suffixes.get(suffix).into_iter().flatten());
+            }
+        }
+    }
+}
+
+// Contains (`*foo*`) and regex transitions combined so a path segment is tested
+// against all of them in one pass, rather than one transition at a time. The
+// contains fragments are searched for with a single Aho-Corasick automaton, and
+// the regexes are compiled into a `RegexSet`. Keeping the two apart lets the
+// fragments be found without running the regex engine at all, which is much
+// faster than adding them to the set as escaped, unanchored regexes. Prefix
+// and suffix transitions aren't included, as `AffixTransitions` already looks
+// them up without testing each one.
+#[derive(Debug, Clone)]
+struct CombinedTransitions {
+    fragments: Option<aho_corasick::AhoCorasick>,
+    // The target of the transition for each fragment in the automaton.
+    fragment_targets: Vec<StateId>,
+    regex_set: Option<regex::RegexSet>,
+    // The target of the transition for each regex in the set.
+    regex_targets: Vec<StateId>,
+}
+
+impl CombinedTransitions {
+    // With fewer transitions than this, testing each one is faster.
+    const MIN_TRANSITIONS: usize = 4;
+
+    // Returns `None` if the transitions can't be combined (for instance, if
+    // the regex set would be too large), in which case they're tested one by
+    // one.
+    fn new(transitions: &[&Transition]) -> Option<Self> {
+        let mut fragments = Vec::new();
+        let mut fragment_targets = Vec::new();
+        let mut regexes = Vec::new();
+        let mut regex_targets = Vec::new();
+        for transition in transitions {
+            match &transition.condition {
+                TransitionCondition::Contains => {
+                    fragments.push(transition.fragment());
+                    fragment_targets.push(transition.target);
+                }
+                TransitionCondition::Regex(re) => {
+                    regexes.push(re.as_str());
+                    regex_targets.push(transition.target);
+                }
+                _ => return None,
+            }
+        }
+
+        let fragments = match fragments.is_empty() {
+            true => None,
+            false => Some(aho_corasick::AhoCorasick::new(fragments).ok()?),
+        };
+        let regex_set = match regexes.is_empty() {
+            true => None,
+            false => Some(regex::RegexSet::new(regexes).ok()?),
+        };
+        Some(Self {
+            fragments,
+            fragment_targets,
+            regex_set,
+            regex_targets,
+        })
+    }
+
+    fn matching_targets(&self, segment: &str, targets: &mut Vec<StateId>) {
+        if let Some(fragments) = &self.fragments {
+            // Fragments may overlap one another, or occur more than once in
+            // the segment, so every occurrence is found and the matching
+            // fragments are deduplicated.
+            let mut matched = fragments
+                .find_overlapping_iter(segment)
+                .map(|m| m.pattern().as_usize())
+                .collect::<Vec<_>>();
+            matched.sort_unstable();
+            matched.dedup();
+            targets.extend(matched.into_iter().map(|idx| self.fragment_targets[idx]));
+        }
+        if let Some(regex_set) = &self.regex_set {
+            targets.extend(
+                regex_set
+                    .matches(segment)
+                    .into_iter()
+                    .map(|idx| self.regex_targets[idx]),
+            );
+        }
+    }
 }
 
 // A nondeterministic finite automaton (NFA) for matching patterns. The
@@ -72,6 +329,13 @@ impl Nfa {
         StateId(id as u32)
     }
 
+    // Prepare the NFA for matching once all patterns have been added.
+    pub(crate) fn compile(&mut self) {
+        for state in &mut self.states {
+            state.combine_transitions();
+        }
+    }
+
     // Given a state id, get an immutable reference to the state.
     pub(crate) fn state(&self, id: StateId) -> &State {
         &self.states[usize::from(id)]
@@ -92,8 +356,11 @@ impl Nfa {
     }
 
     // Return an iterator over all transitions from the given state id.
-    pub(crate) fn transitions_from(&self, state_id: StateId) -> impl Iterator<Item = &Transition> {
-        self.state(state_id).transitions.iter()
+    pub(crate) fn transitions_from(
+        &self,
+        state_id: StateId,
+    ) -> impl Iterator<Item = Cow<'_, Transition>> {
+        self.state(state_id).transitions()
     }
 
     // Get the epsilon transition for the given state id.
@@ -101,8 +368,7 @@ impl Nfa {
         self.state(state_id).epsilon_transition
     }
 
-    // Return an iterater over all states. Only used in tests.
-    #[cfg(test)]
+    // Return an iterater over all states.
     pub(crate) fn states_iter(&self) -> std::slice::Iter<'_, State> {
         self.states.iter()
     }
@@ -127,9 +393,79 @@ impl Transition {
         }
     }
 
+    // Create a transition for a segment known to be a literal, skipping the
+    // check for wildcards.
+    fn literal(path_segment: &str, target: StateId) -> Transition {
+        Self {
+            path_segment: path_segment.to_owned(),
+            condition: TransitionCondition::Literal,
+            target,
+        }
+    }
+
     pub(crate) fn is_match(&self, candidate: &str) -> bool {
         self.condition.is_match(&self.path_segment, candidate)
     }
+
+    pub(crate) fn kind(&self) -> TransitionKind {
+        match self.condition {
+            TransitionCondition::Unconditional => TransitionKind::Unconditional,
+            TransitionCondition::Literal => TransitionKind::Literal,
+            TransitionCondition::Prefix => TransitionKind::Prefix,
+            TransitionCondition::Suffix => TransitionKind::Suffix,
+            TransitionCondition::Contains => TransitionKind::Contains,
+            TransitionCondition::Regex(_) => TransitionKind::Regex,
+        }
+    }
+
+    // Returns true if every path segment matched by this transition is also
+    // matched by `other`. This is conservative: it may return false for some
+    // transitions that are in fact subsets, but never returns true for ones that
+    // aren't.
+    pub(crate) fn is_subset_of(&self, other: &Transition) -> bool {
+        use TransitionCondition::*;
+
+        if self.path_segment == other.path_segment {
+            return true;
+        }
+        let (a, b) = (self.fragment(), other.fragment());
+        match (&self.condition, &other.condition) {
+            (_, Unconditional) => true,
+            (Literal, _) => other.is_match(&self.path_segment),
+            (Prefix, Prefix) => a.starts_with(b),
+            (Suffix, Suffix) => a.ends_with(b),
+            (Prefix | Suffix | Contains, Contains) => a.contains(b),
+            _ => false,
+        }
+    }
+
+    // Returns true if no path segment can be matched by both this transition
+    // and `other`. Like `is_subset_of`, this is conservative, and may return
+    // false for transitions that are in fact disjoint.
+    pub(crate) fn is_disjoint_from(&self, other: &Transition) -> bool {
+        use TransitionCondition::*;
+
+        let (a, b) = (self.fragment(), other.fragment());
+        match (&self.condition, &other.condition) {
+            (Literal, _) => !other.is_match(&self.path_segment),
+            (_, Literal) => !self.is_match(&other.path_segment),
+            (Prefix, Prefix) => !a.starts_with(b) && !b.starts_with(a),
+            (Suffix, Suffix) => !a.ends_with(b) && !b.ends_with(a),
+            _ => false,
+        }
+    }
+
+    // The literal part of the path segment that the condition tests for, with
+    // any leading or trailing wildcards removed.
+    pub(crate) fn fragment(&self) -> &str {
+        let segment = self.path_segment.as_str();
+        match self.condition {
+            TransitionCondition::Prefix => &segment[..segment.len() - 1],
+            TransitionCondition::Suffix => &segment[1..],
+            TransitionCondition::Contains => &segment[1..segment.len() - 1],
+            _ => segment,
+        }
+    }
 }
 
 // Different types of transitions have different conditions for matching. While
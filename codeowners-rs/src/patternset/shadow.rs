@@ -0,0 +1,174 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::nfa::{Nfa, StateId};
+
+// Find patterns that are shadowed by other patterns, i.e. every path matched by
+// the pattern is also matched by at least one of the patterns that may shadow
+// it. `can_shadow(a, b)` determines whether pattern `b` is allowed to shadow
+// pattern `a`. Returns (pattern, shadowing patterns) pairs.
+//
+// This works by exploring the product of the sub-automaton that matches the
+// pattern in question (the "pattern side") and the whole NFA (the "shadow
+// side"). Path segments are treated symbolically: each transition leaving the
+// pattern side's current states defines a class of segments. For each class we
+// over-approximate the pattern side's next states and under-approximate the
+// shadow side's next states, so when the pattern side reaches a terminal state,
+// the shadow side is guaranteed to be in a terminal state for some shadowing
+// pattern for every concrete path. The analysis is conservative: it may miss
+// some shadowed patterns, but won't report patterns that aren't shadowed.
+pub(super) fn shadowed_patterns(
+    nfa: &Nfa,
+    can_shadow: impl Fn(usize, usize) -> bool,
+) -> Vec<(usize, Vec<usize>)> {
+    // Build a reverse adjacency list so we can find the states that lead to
+    // each pattern's terminal states.
+    let state_count = nfa.states_iter().len();
+    let mut predecessors = vec![Vec::new(); state_count];
+    let mut terminal_states: HashMap<usize, Vec<StateId>> = HashMap::new();
+    for (idx, state) in nfa.states_iter().enumerate() {
+        let state_id = StateId(idx as u32);
+        for target in state.targets() {
+            predecessors[usize::from(target)].push(state_id);
+        }
+        if let Some(epsilon_id) = state.epsilon_transition {
+            predecessors[usize::from(epsilon_id)].push(state_id);
+        }
+        for &pattern_id in state.terminal_for_patterns.iter().flatten() {
+            terminal_states
+                .entry(pattern_id)
+                .or_default()
+                .push(state_id);
+        }
+    }
+
+    let mut pattern_ids = terminal_states.keys().copied().collect::<Vec<_>>();
+    pattern_ids.sort_unstable();
+
+    let mut shadowed = Vec::new();
+    for pattern_id in pattern_ids {
+        let live = live_states(&predecessors, &terminal_states[&pattern_id]);
+        let analysis = Analysis {
+            nfa,
+            pattern_id,
+            live: &live,
+            can_shadow: &can_shadow,
+        };
+        if let Some(shadowing) = analysis.run() {
+            shadowed.push((pattern_id, shadowing));
+        }
+    }
+    shadowed
+}
+
+// Return a bitmap of the states from which one of the given terminal states
+// can be reached.
+fn live_states(predecessors: &[Vec<StateId>], terminal_states: &[StateId]) -> Vec<bool> {
+    let mut live = vec![false; predecessors.len()];
+    let mut stack = terminal_states.to_vec();
+    while let Some(state_id) = stack.pop() {
+        let idx = usize::from(state_id);
+        if !live[idx] {
+            live[idx] = true;
+            stack.extend(predecessors[idx].iter().copied());
+        }
+    }
+    live
+}
+
+struct Analysis<'a, F> {
+    nfa: &'a Nfa,
+    pattern_id: usize,
+    live: &'a [bool],
+    can_shadow: &'a F,
+}
+
+impl<F: Fn(usize, usize) -> bool> Analysis<'_, F> {
+    // Returns the shadowing patterns if the pattern is shadowed.
+    fn run(&self) -> Option<Vec<usize>> {
+        let initial = self.nfa.initial_states();
+        let pattern_states = self.normalize(initial.iter().copied().filter(|&s| self.is_live(s)));
+        let shadow_states = self.normalize(initial.iter().copied());
+
+        let mut shadowing = BTreeSet::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![(pattern_states, shadow_states)];
+        while let Some((pattern_states, shadow_states)) = stack.pop() {
+            if !visited.insert((pattern_states.clone(), shadow_states.clone())) {
+                continue;
+            }
+
+            // If the pattern matches here, a shadowing pattern must too.
+            if pattern_states.iter().any(|&s| self.is_terminal(s)) {
+                let terminal_for = shadow_states
+                    .iter()
+                    .flat_map(|&s| self.terminal_patterns(s))
+                    .filter(|&p| (self.can_shadow)(self.pattern_id, p))
+                    .collect::<Vec<_>>();
+                if terminal_for.is_empty() {
+                    return None;
+                }
+                shadowing.extend(terminal_for);
+            }
+
+            let transitions = pattern_states
+                .iter()
+                .flat_map(|&s| self.nfa.transitions_from(s))
+                .filter(|t| self.is_live(t.target))
+                .collect::<Vec<_>>();
+            for class in &transitions {
+                // Segments in this class that also belong to a strictly
+                // narrower class are handled when we visit that class.
+                let next_pattern_states = self.normalize(
+                    transitions
+                        .iter()
+                        .filter(|t| !t.is_disjoint_from(class))
+                        .filter(|t| !t.is_subset_of(class) || class.is_subset_of(t))
+                        .map(|t| t.target),
+                );
+                let next_shadow_states = self.normalize(
+                    shadow_states
+                        .iter()
+                        .flat_map(|&s| self.nfa.state(s).targets_covering(class)),
+                );
+                if next_shadow_states.is_empty() {
+                    return None;
+                }
+                stack.push((next_pattern_states, next_shadow_states));
+            }
+        }
+
+        Some(shadowing.into_iter().collect())
+    }
+
+    fn is_live(&self, state_id: StateId) -> bool {
+        self.live[usize::from(state_id)]
+    }
+
+    fn is_terminal(&self, state_id: StateId) -> bool {
+        self.terminal_patterns(state_id)
+            .any(|p| p == self.pattern_id)
+    }
+
+    fn terminal_patterns(&self, state_id: StateId) -> impl Iterator<Item = usize> + '_ {
+        self.nfa
+            .state(state_id)
+            .terminal_for_patterns
+            .iter()
+            .flatten()
+            .copied()
+    }
+
+    // Follow epsilon edges (as the matcher does), then sort and deduplicate the
+    // states so equivalent sets compare equal.
+    fn normalize(&self, states: impl Iterator<Item = StateId>) -> Vec<StateId> {
+        let mut states = states.collect::<Vec<_>>();
+        let epsilon_states = states
+            .iter()
+            .flat_map(|&s| self.nfa.epsilon_transitions_from(s))
+            .collect::<Vec<_>>();
+        states.extend(epsilon_states);
+        states.sort_unstable_by_key(|s| s.0);
+        states.dedup();
+        states
+    }
+}
@@ -0,0 +1,129 @@
+use std::{collections::HashSet, path::Path};
+
+use super::nfa::{Nfa, StateId, Transition};
+
+/// The kind of NFA transition taken by a [`TraceStep`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    /// A pattern segment with no wildcards, matched exactly.
+    Literal,
+    /// A pattern segment ending in a wildcard, such as `foo-*`.
+    Prefix,
+    /// A pattern segment starting with a wildcard, such as `*.rs`.
+    Suffix,
+    /// A pattern segment starting and ending with a wildcard, such as `*test*`.
+    Contains,
+    /// Any other pattern segment, matched with a regex.
+    Regex,
+    /// A `*` segment, which matches any path component. Components matched by
+    /// a `**` segment also use unconditional transitions, from a state back to
+    /// itself.
+    Unconditional,
+    /// An epsilon transition, taken without consuming a path component when
+    /// entering a `**` segment.
+    Epsilon,
+}
+
+/// A single transition taken while matching a path. State ids identify states
+/// in the matcher's NFA; they're only useful for comparing steps, as the NFA
+/// itself isn't exposed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    /// The index of the path component consumed by the transition, or `None`
+    /// for epsilon transitions, which don't consume a component.
+    pub component: Option<usize>,
+    /// The state the transition leaves.
+    pub from: usize,
+    /// The state the transition enters.
+    pub to: usize,
+    /// The pattern segment that matched the component. This is `**` for
+    /// epsilon transitions and for components matched by a `**` segment.
+    pub segment: String,
+    pub kind: TransitionKind,
+}
+
+/// The sequence of transitions that led to a pattern matching a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchTrace {
+    /// The index of the matching pattern.
+    pub pattern: usize,
+    pub steps: Vec<TraceStep>,
+}
+
+// Match a path against the NFA, recording the transitions taken to reach each
+// state. When several routes lead to the same state, only the first is kept,
+// so each matching pattern gets a single trace. This doesn't use the matcher's
+// transition cache, as the cache doesn't record how states were reached.
+pub(super) fn trace_matching_patterns(nfa: &Nfa, path: &Path) -> Vec<MatchTrace> {
+    let mut active = vec![(Nfa::START_STATE, Vec::new())];
+    enter_epsilon(nfa, &mut active, &mut HashSet::new());
+
+    for (idx, component) in path.iter().enumerate() {
+        let component = component.to_string_lossy();
+        let mut next = Vec::new();
+        let mut seen = HashSet::new();
+        for (state_id, steps) in &active {
+            for transition in nfa.transitions_from(*state_id) {
+                if !transition.is_match(&component) || !seen.insert(transition.target) {
+                    continue;
+                }
+                let mut steps = steps.clone();
+                steps.push(step(idx, *state_id, &transition));
+                next.push((transition.target, steps));
+                enter_epsilon(nfa, &mut next, &mut seen);
+            }
+        }
+        active = next;
+    }
+
+    let mut traces = Vec::new();
+    for (state_id, steps) in active {
+        for &pattern in nfa.state(state_id).terminal_for_patterns.iter().flatten() {
+            traces.push(MatchTrace {
+                pattern,
+                steps: steps.clone(),
+            });
+        }
+    }
+    traces.sort_by_key(|trace| trace.pattern);
+    traces
+}
+
+// Follow the epsilon transition (if any) from the most recently entered state.
+fn enter_epsilon(
+    nfa: &Nfa,
+    active: &mut Vec<(StateId, Vec<TraceStep>)>,
+    seen: &mut HashSet<StateId>,
+) {
+    let (state_id, steps) = active.last().expect("a state was just entered");
+    let Some(epsilon_id) = nfa.epsilon_transitions_from(*state_id) else {
+        return;
+    };
+    if seen.insert(epsilon_id) {
+        let mut steps = steps.clone();
+        steps.push(TraceStep {
+            component: None,
+            from: usize::from(*state_id),
+            to: usize::from(epsilon_id),
+            segment: "**".to_owned(),
+            kind: TransitionKind::Epsilon,
+        });
+        active.push((epsilon_id, steps));
+    }
+}
+
+fn step(component: usize, from: StateId, transition: &Transition) -> TraceStep {
+    // The builder only adds self-loops to the states entered by `**` segments
+    let segment = if transition.target == from {
+        "**".to_owned()
+    } else {
+        transition.path_segment.clone()
+    };
+    TraceStep {
+        component: Some(component),
+        from: usize::from(from),
+        to: usize::from(transition.target),
+        segment,
+        kind: transition.kind(),
+    }
+}
@@ -1,4 +1,9 @@
-use std::path::Path;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+    path::Path,
+    sync::Arc,
+};
 
 use once_cell::sync::Lazy;
 use regex::Regex;
@@ -20,23 +25,80 @@ use crate::patternset;
 #[derive(Clone)]
 pub struct RuleSet {
     rules: Vec<Rule>,
+    sections: Vec<Section>,
+    // The section group each rule belongs to. Sections with the same name
+    // (compared case-insensitively) share a group, and rules that aren't in any
+    // section belong to the implicit default group, 0.
+    rule_groups: Vec<usize>,
+    // The index of the first section in each group, or `None` for the default
+    // group.
+    group_sections: Vec<Option<usize>>,
+    // The indices of the rules listing each owner, keyed by the owner's
+    // lowercased value.
+    owner_index: HashMap<String, Vec<usize>>,
     matcher: patternset::Matcher,
 }
 
 impl RuleSet {
     /// Construct a `RuleSet` from a `Vec` of `Rule`s.
     pub fn new(rules: Vec<Rule>) -> Self {
+        Self::with_sections(rules, Vec::new())
+    }
+
+    /// Construct a `RuleSet` from a `Vec` of `Rule`s and a `Vec` of GitLab-style
+    /// `Section`s. Each section's `rules` range indicates which rules belong to
+    /// it; rules outside of any section belong to an implicit default section.
+    pub fn with_sections(rules: Vec<Rule>, sections: Vec<Section>) -> Self {
         let mut builder = patternset::Builder::new();
         for rule in &rules {
             builder.add(&rule.pattern);
         }
         let matcher = builder.build();
-        Self { rules, matcher }
+
+        let mut rule_groups = vec![0; rules.len()];
+        let mut group_ids = HashMap::new();
+        let mut group_sections = vec![None];
+        for (section_idx, section) in sections.iter().enumerate() {
+            let group_id = *group_ids
+                .entry(section.name.to_lowercase())
+                .or_insert_with(|| {
+                    group_sections.push(Some(section_idx));
+                    group_sections.len() - 1
+                });
+            let end = section.rules.end.min(rules.len());
+            let start = section.rules.start.min(end);
+            rule_groups[start..end].fill(group_id);
+        }
+
+        let mut owner_index: HashMap<String, Vec<usize>> = HashMap::new();
+        for (idx, rule) in rules.iter().enumerate() {
+            for owner in unique_owners(&rule.owners) {
+                owner_index.entry(owner).or_default().push(idx);
+            }
+        }
+
+        Self {
+            rules,
+            sections,
+            rule_groups,
+            group_sections,
+            owner_index,
+            matcher,
+        }
+    }
+
+    /// Returns the sections in the rule set, in the order they were defined.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
     }
 
     /// Returns the matching rule (if any) for the given path. If multiple rules
     /// match the path, the last matching rule in the CODEOWNERS file will be
     /// returned. If no rules match the path, `None` will be returned.
+    ///
+    /// This follows GitHub's semantics, where a single rule wins. For rule sets
+    /// with GitLab-style sections, use
+    /// [`matching_rules`](fn@RuleSet::matching_rules) instead.
     pub fn matching_rule(&self, path: impl AsRef<Path>) -> Option<&Rule> {
         self.matcher
             .matching_patterns(path)
@@ -48,13 +110,201 @@ impl RuleSet {
     /// Returns the owners for the given path, or `None` if no rules match the
     /// path or the matching rule has no owners.
     pub fn owners(&self, path: impl AsRef<Path>) -> Option<&[Owner]> {
-        return self.matching_rule(path).and_then(|rule| {
+        self.matching_rule(path).and_then(|rule| {
             if rule.owners.is_empty() {
                 None
             } else {
                 Some(rule.owners.as_ref())
             }
-        });
+        })
+    }
+
+    /// Returns the winning rule from each section that has a rule matching the
+    /// given path, along with the rules' indices. This follows GitLab's
+    /// semantics, where the last matching rule in each section applies.
+    /// Sections with the same name are treated as a single section, and rules
+    /// before the first section header form their own section. The results
+    /// are ordered by the position of each section's first header.
+    ///
+    /// For rule sets without sections this returns at most one rule, the same
+    /// one returned by [`matching_rule`](fn@RuleSet::matching_rule).
+    pub fn matching_rules(&self, path: impl AsRef<Path>) -> Vec<(usize, &Rule)> {
+        self.group_winners(path)
+            .into_iter()
+            .map(|(_, idx)| (idx, &self.rules[idx]))
+            .collect()
+    }
+
+    /// Returns a [`SectionMatch`] for each section that has a rule matching the
+    /// given path. Each result contains the section's winning rule along with
+    /// the number of approvals the section requires, which is useful for
+    /// working out the full set of approval groups for a change. Grouping and
+    /// ordering follow the same rules as
+    /// [`matching_rules`](fn@RuleSet::matching_rules).
+    pub fn section_matches(&self, path: impl AsRef<Path>) -> Vec<SectionMatch<'_>> {
+        self.group_winners(path)
+            .into_iter()
+            .map(|(group, idx)| {
+                let section = self.group_sections[group].map(|s| &self.sections[s]);
+                let rule = &self.rules[idx];
+                let optional = section.is_some_and(|s| s.optional);
+                let required_approvals = if optional || rule.owners.is_empty() {
+                    0
+                } else {
+                    section.and_then(|s| s.approvals).unwrap_or(1)
+                };
+                SectionMatch {
+                    section,
+                    rule_index: idx,
+                    rule,
+                    required_approvals,
+                    optional,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the rules that can never be the winning rule for any path,
+    /// because every path they match is also matched by later rules in the same
+    /// section. Each result is the index of a shadowed rule along with the
+    /// indices of the rules that shadow it. This works on the patterns alone,
+    /// so it doesn't need a list of paths, but it's conservative and may miss
+    /// rules shadowed by complex wildcard patterns.
+    pub fn shadowed_rules(&self) -> Vec<(usize, Vec<usize>)> {
+        self.matcher.shadowed_patterns(|shadowed, shadowing| {
+            shadowing > shadowed && self.rule_groups[shadowing] == self.rule_groups[shadowed]
+        })
+    }
+
+    /// Returns the rules that list the given owner, along with their indices.
+    /// Owners are compared case-insensitively.
+    pub fn rules_owned_by(&self, owner: &str) -> Vec<(usize, &Rule)> {
+        self.owner_index
+            .get(&owner.to_lowercase())
+            .map(|indices| indices.iter().map(|&idx| (idx, &self.rules[idx])).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the indices of the paths in `paths` that are effectively owned
+    /// by the given owner, meaning the owner is listed on a winning rule for
+    /// the path (see [`matching_rules`](fn@RuleSet::matching_rules)). Paths
+    /// where the owner is only listed on rules that are overridden by later
+    /// rules aren't included. Owners are compared case-insensitively.
+    pub fn paths_owned_by<P: AsRef<Path>>(&self, owner: &str, paths: &[P]) -> Vec<usize> {
+        let Some(owned_rules) = self.owner_index.get(&owner.to_lowercase()) else {
+            return Vec::new();
+        };
+        (0..paths.len())
+            .filter(|&i| {
+                self.group_winners(&paths[i])
+                    .iter()
+                    .any(|(_, idx)| owned_rules.binary_search(idx).is_ok())
+            })
+            .collect()
+    }
+
+    /// Suggests a small set of reviewers whose approval covers every owned path
+    /// in `paths`, such as the files changed in a pull request. A path is
+    /// covered once one of the owners of its matching rule has been chosen, or
+    /// for sections requiring several approvals, once enough of them have.
+    /// Optional sections don't need to be covered.
+    ///
+    /// Finding the smallest covering set is NP-hard, so this greedily picks
+    /// the owner covering the most uncovered paths until everything is
+    /// covered. Ties are broken by choosing the owner that sorts first
+    /// (ignoring case), so results are deterministic. Owners are compared
+    /// case-insensitively.
+    ///
+    /// # Example
+    /// ```
+    /// use codeowners_rs::parse;
+    ///
+    /// let ruleset = parse("
+    /// *.rs @rustaceans @alice
+    /// /docs/ @writers @alice
+    /// ").into_ruleset();
+    ///
+    /// let suggestion = ruleset.suggest_reviewers(&["src/main.rs", "docs/README.md", "Makefile"]);
+    /// assert_eq!(suggestion.reviewers[0].value, "@alice");
+    /// assert_eq!(suggestion.unowned, vec![2]);
+    /// ```
+    pub fn suggest_reviewers<P: AsRef<Path>>(&self, paths: &[P]) -> ReviewerSuggestion<'_> {
+        // Paths won by the same rule need the same approvals, so there's one
+        // requirement per winning rule.
+        let mut requirements: BTreeMap<usize, Requirement> = BTreeMap::new();
+        let mut unowned = Vec::new();
+        for (i, path) in paths.iter().enumerate() {
+            let matches = self.section_matches(path);
+            if matches.iter().all(|m| m.rule.owners.is_empty()) {
+                unowned.push(i);
+                continue;
+            }
+            for m in matches.into_iter().filter(|m| m.required_approvals > 0) {
+                let requirement = requirements.entry(m.rule_index).or_insert_with(|| {
+                    let owners = unique_owners(&m.rule.owners);
+                    Requirement {
+                        needed: m.required_approvals.min(owners.len()),
+                        owners,
+                        paths: 0,
+                    }
+                });
+                requirement.paths += 1;
+            }
+        }
+
+        // Candidates are keyed by lowercased value, so iterating in order gives
+        // the tie-break.
+        let mut candidates: BTreeMap<String, &Owner> = BTreeMap::new();
+        for &rule_idx in requirements.keys() {
+            for owner in &self.rules[rule_idx].owners {
+                candidates
+                    .entry(owner.value.to_lowercase())
+                    .or_insert(owner);
+            }
+        }
+
+        let mut reviewers = Vec::new();
+        loop {
+            let mut best: Option<(&String, usize)> = None;
+            for key in candidates.keys() {
+                let covered = requirements
+                    .values()
+                    .filter(|r| r.needed > 0 && r.owners.contains(key))
+                    .map(|r| r.paths)
+                    .sum();
+                if covered > best.map_or(0, |(_, c)| c) {
+                    best = Some((key, covered));
+                }
+            }
+            let Some((key, _)) = best else {
+                break;
+            };
+
+            let key = key.clone();
+            for requirement in requirements.values_mut() {
+                if requirement.needed > 0 && requirement.owners.contains(&key) {
+                    requirement.needed -= 1;
+                }
+            }
+            reviewers.push(candidates.remove(&key).expect("key is a candidate"));
+        }
+
+        ReviewerSuggestion { reviewers, unowned }
+    }
+
+    // Match the path and return the highest matching rule index for each
+    // section group, as (group, rule index) pairs ordered by group.
+    fn group_winners(&self, path: impl AsRef<Path>) -> Vec<(usize, usize)> {
+        let mut winners: Vec<Option<usize>> = vec![None; self.group_sections.len()];
+        for idx in self.matcher.matching_patterns(path) {
+            let winner = &mut winners[self.rule_groups[idx]];
+            *winner = Some(winner.map_or(idx, |w| w.max(idx)));
+        }
+        winners
+            .into_iter()
+            .enumerate()
+            .filter_map(|(group, winner)| winner.map(|idx| (group, idx)))
+            .collect()
     }
 
     /// Returns the all rules that match the given path along with their indices.
@@ -67,6 +317,49 @@ impl RuleSet {
             .map(|&idx| (idx, &self.rules[idx]))
             .collect()
     }
+
+    /// Returns the rules that match the given path, along with a trace of how
+    /// each rule's pattern matched the path. Each trace's `pattern` is the
+    /// index of the rule. This is slower than
+    /// [`all_matching_rules`](fn@RuleSet::all_matching_rules), and is intended
+    /// for explaining matches. See
+    /// [`crate::patternset::Matcher::trace_matching_patterns`].
+    pub fn trace_matching_rules(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Vec<(&Rule, patternset::MatchTrace)> {
+        self.matcher
+            .trace_matching_patterns(path)
+            .into_iter()
+            .map(|trace| (&self.rules[trace.pattern], trace))
+            .collect()
+    }
+
+    /// Compile the path matcher into a deterministic automaton with at most
+    /// `max_states` states, to speed up matching against large rule sets.
+    /// Returns whether compilation succeeded. See
+    /// [`crate::patternset::Matcher::determinize`].
+    pub fn determinize(&mut self, max_states: usize) -> bool {
+        self.matcher.determinize(max_states)
+    }
+
+    /// Set the maximum number of entries in the path matcher's cache, clearing
+    /// it. See [`crate::patternset::Matcher::set_cache_capacity`].
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.matcher.set_cache_capacity(capacity);
+    }
+
+    /// Clear the path matcher's cache. See
+    /// [`crate::patternset::Matcher::clear_cache`].
+    pub fn clear_cache(&self) {
+        self.matcher.clear_cache();
+    }
+
+    /// Returns statistics about the path matcher's cache. See
+    /// [`crate::patternset::Matcher::cache_stats`].
+    pub fn cache_stats(&self) -> patternset::CacheStats {
+        self.matcher.cache_stats()
+    }
 }
 
 // `Rule` is an individual CODEOWNERS rule. It contains a pattern and a list of
@@ -77,6 +370,64 @@ pub struct Rule {
     pub owners: Vec<Owner>,
 }
 
+/// `Section` is a GitLab CODEOWNERS section. Each section contributes its own
+/// winning rule for a path, and non-optional sections require approval from
+/// one of the owners of that rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub optional: bool,
+    pub approvals: Option<usize>,
+    pub default_owners: Vec<Owner>,
+    /// The indices of the rules that belong to this section.
+    pub rules: Range<usize>,
+}
+
+/// `SectionMatch` is the result of matching a path against a single section of
+/// a `RuleSet`. `section` is `None` for rules that appear before the first
+/// section header. Optional sections, and winning rules without owners, don't
+/// require any approvals; otherwise a section requires the number of approvals
+/// given in its header, defaulting to one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionMatch<'a> {
+    pub section: Option<&'a Section>,
+    pub rule_index: usize,
+    pub rule: &'a Rule,
+    pub required_approvals: usize,
+    pub optional: bool,
+}
+
+/// `ReviewerSuggestion` is the result of
+/// [`suggest_reviewers`](fn@RuleSet::suggest_reviewers).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewerSuggestion<'a> {
+    /// The suggested reviewers, in the order they were chosen. Earlier
+    /// reviewers cover more of the paths.
+    pub reviewers: Vec<&'a Owner>,
+    /// The indices of the paths that have no owners.
+    pub unowned: Vec<usize>,
+}
+
+// A set of owners, some number of which need to approve a change.
+struct Requirement {
+    // Lowercased owner values
+    owners: Vec<String>,
+    needed: usize,
+    // The number of paths that have this requirement
+    paths: usize,
+}
+
+// The lowercased values of a rule's owners, without duplicates.
+fn unique_owners(owners: &[Owner]) -> Vec<String> {
+    let mut values = owners
+        .iter()
+        .map(|o| o.value.to_lowercase())
+        .collect::<Vec<_>>();
+    values.sort();
+    values.dedup();
+    values
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Owner {
     pub value: String,
@@ -90,10 +441,82 @@ impl Owner {
 }
 
 static EMAIL_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"\A[A-Z0-9a-z\._'%\+\-]+@[A-Za-z0-9\.\-]+\.[A-Za-z]{2,6}\z").unwrap());
+    Lazy::new(|| Regex::new(r"\A[A-Z0-9a-z\._'%\+\-]+@[A-Za-z0-9\.\-]+\.[A-Za-z]{2,}\z").unwrap());
 static USERNAME_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\A@[a-zA-Z0-9\-_]+\z").unwrap());
 static TEAM_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"\A@[a-zA-Z0-9\-]+/[a-zA-Z0-9\-_]+\z").unwrap());
+static ENTERPRISE_USERNAME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\A@[a-zA-Z0-9\-_\.]+\z").unwrap());
+static ENTERPRISE_TEAM_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\A@[a-zA-Z0-9\-_\.]+/[a-zA-Z0-9\-_\.]+\z").unwrap());
+static GITLAB_USERNAME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\A@[a-zA-Z0-9\-_\.]+\z").unwrap());
+static GITLAB_GROUP_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\A@[a-zA-Z0-9\-_\.]+(/[a-zA-Z0-9\-_\.]+)+\z").unwrap());
+
+/// A function that returns the kind of a valid owner, or `None` if the owner
+/// is invalid. Used by [`OwnerPolicy::Custom`].
+pub type OwnerValidator = Arc<dyn Fn(&str) -> Option<OwnerKind> + Send + Sync>;
+
+/// The grammar used to decide which owners are valid, and what kind of owner
+/// each one is. All of the built-in policies accept email addresses.
+#[derive(Clone, Default)]
+pub enum OwnerPolicy {
+    /// GitHub usernames such as `@octocat` and teams such as `@org/team`.
+    #[default]
+    GitHub,
+    /// Like `GitHub`, but usernames and team names may also contain dots and
+    /// underscores, as they can on GitHub Enterprise Server.
+    GitHubEnterprise,
+    /// GitLab usernames and groups, which may contain dots and be nested, such
+    /// as `@org/group/subgroup`. Nested groups are treated as teams.
+    GitLab,
+    /// A custom validator, which returns the kind of a valid owner, or `None`
+    /// if the owner is invalid.
+    Custom(OwnerValidator),
+}
+
+impl OwnerPolicy {
+    /// Returns the kind of `value` under this policy, or `None` if it isn't a
+    /// valid owner.
+    pub fn owner_kind(&self, value: &str) -> Option<OwnerKind> {
+        let (username_regex, team_regex) = match self {
+            OwnerPolicy::GitHub => (&USERNAME_REGEX, &TEAM_REGEX),
+            OwnerPolicy::GitHubEnterprise => (&ENTERPRISE_USERNAME_REGEX, &ENTERPRISE_TEAM_REGEX),
+            OwnerPolicy::GitLab => (&GITLAB_USERNAME_REGEX, &GITLAB_GROUP_REGEX),
+            OwnerPolicy::Custom(validator) => return validator(value),
+        };
+        if EMAIL_REGEX.is_match(value) {
+            Some(OwnerKind::Email)
+        } else if username_regex.is_match(value) {
+            Some(OwnerKind::User)
+        } else if team_regex.is_match(value) {
+            Some(OwnerKind::Team)
+        } else {
+            None
+        }
+    }
+
+    /// Parse `value` into an `Owner`, returning an error if it isn't a valid
+    /// owner under this policy.
+    pub fn parse_owner(&self, value: String) -> Result<Owner, InvalidOwnerError> {
+        match self.owner_kind(&value) {
+            Some(kind) => Ok(Owner::new(value, kind)),
+            None => Err(InvalidOwnerError { value }),
+        }
+    }
+}
+
+impl std::fmt::Debug for OwnerPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OwnerPolicy::GitHub => write!(f, "GitHub"),
+            OwnerPolicy::GitHubEnterprise => write!(f, "GitHubEnterprise"),
+            OwnerPolicy::GitLab => write!(f, "GitLab"),
+            OwnerPolicy::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct InvalidOwnerError {
@@ -111,22 +534,269 @@ impl std::error::Error for InvalidOwnerError {}
 impl TryFrom<String> for Owner {
     type Error = InvalidOwnerError;
 
+    /// Parse an owner using the GitHub [`OwnerPolicy`].
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        if EMAIL_REGEX.is_match(&value) {
-            Ok(Self::new(value, OwnerKind::Email))
-        } else if USERNAME_REGEX.is_match(&value) {
-            Ok(Self::new(value, OwnerKind::User))
-        } else if TEAM_REGEX.is_match(&value) {
-            Ok(Self::new(value, OwnerKind::Team))
-        } else {
-            Err(InvalidOwnerError { value })
-        }
+        OwnerPolicy::GitHub.parse_owner(value)
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OwnerKind {
     User,
     Team,
     Email,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{parse_with_options, Dialect, ParseOptions};
+
+    #[test]
+    fn test_gitlab_sections() {
+        let source = "
+*.rs @rustaceans
+/docs/ @writers
+
+[Docs] @docs-team
+*.md
+/docs/internal/ @internal
+
+^[Frontend][2]
+*.js @frontend
+
+[docs]
+/docs/api/ @api-team
+";
+        let options = ParseOptions {
+            dialect: Dialect::GitLab,
+            ..Default::default()
+        };
+        let ruleset = parse_with_options(source, &options).into_ruleset();
+
+        let matches = |path| {
+            ruleset
+                .matching_rules(path)
+                .into_iter()
+                .map(|(_, rule)| rule.pattern.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(matches("src/main.rs"), vec!["*.rs"]);
+        assert_eq!(matches("docs/README.md"), vec!["/docs/", "*.md"]);
+        assert_eq!(matches("docs/api/README.md"), vec!["/docs/", "/docs/api/"]);
+        assert_eq!(matches("docs/app.js"), vec!["/docs/", "*.js"]);
+        assert_eq!(matches("Makefile"), Vec::<&str>::new());
+
+        // Rules without owners inherit the section's default owners
+        let (_, rule) = ruleset.matching_rules("README.md")[0];
+        assert_eq!(rule.owners[0].value, "@docs-team");
+
+        assert_eq!(ruleset.sections().len(), 3);
+        assert_eq!(ruleset.sections()[1].approvals, Some(2));
+        assert!(ruleset.sections()[1].optional);
+        assert_eq!(ruleset.sections()[2].rules, 5..6);
+    }
+
+    #[test]
+    fn test_shadowed_rules() {
+        let source = "
+/docs/api/ @a
+/docs/ @b
+
+[Docs]
+/src/docs/ @c
+*.md @d
+
+[Source]
+/src/ @e
+
+[docs]
+/src/ @f
+";
+        let options = ParseOptions {
+            dialect: Dialect::GitLab,
+            ..Default::default()
+        };
+        let ruleset = parse_with_options(source, &options).into_ruleset();
+
+        // Rules are only shadowed by later rules in the same section
+        assert_eq!(ruleset.shadowed_rules(), vec![(0, vec![1]), (2, vec![5])]);
+    }
+
+    #[test]
+    fn test_owned_by() {
+        let source = "
+*.rs @a @b
+/docs/ @A
+/docs/api/ @b
+/vendor/ @c
+/vendor/ @d
+";
+        let ruleset = crate::parse(source).into_ruleset();
+        let rules = |owner| {
+            ruleset
+                .rules_owned_by(owner)
+                .into_iter()
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rules("@a"), vec![0, 1]);
+        assert_eq!(rules("@B"), vec![0, 2]);
+        assert_eq!(rules("@c"), vec![3]);
+        assert_eq!(rules("@nobody"), Vec::<usize>::new());
+
+        let paths = [
+            "main.rs",
+            "docs/README.md",
+            "docs/api/index.md",
+            "docs/api/main.rs",
+            "vendor/lib.rs",
+        ];
+        assert_eq!(ruleset.paths_owned_by("@a", &paths), vec![0, 1]);
+        assert_eq!(ruleset.paths_owned_by("@b", &paths), vec![0, 2, 3]);
+        // @c's rule is always overridden by the @d rule
+        assert_eq!(ruleset.paths_owned_by("@c", &paths), Vec::<usize>::new());
+        assert_eq!(ruleset.paths_owned_by("@d", &paths), vec![4]);
+    }
+
+    #[test]
+    fn test_suggest_reviewers() {
+        let source = "
+*.rs @b @a
+/docs/ @c @B
+/src/ @d
+/vendor/
+
+[Docs][2]
+*.md @x @y @z
+
+^[Optional]
+*.js @o
+";
+        let options = ParseOptions {
+            dialect: Dialect::GitLab,
+            ..Default::default()
+        };
+        let ruleset = parse_with_options(source, &options).into_ruleset();
+        let reviewers = |paths: &[&str]| {
+            let suggestion = ruleset.suggest_reviewers(paths);
+            let reviewers = suggestion
+                .reviewers
+                .iter()
+                .map(|o| o.value.as_str())
+                .collect::<Vec<_>>();
+            (reviewers, suggestion.unowned)
+        };
+
+        // Ties are broken alphabetically, ignoring case
+        assert_eq!(reviewers(&["main.rs"]), (vec!["@a"], vec![]));
+        // Owners covering more paths are preferred
+        assert_eq!(reviewers(&["main.rs", "docs/x.rs"]), (vec!["@b"], vec![]));
+        assert_eq!(
+            reviewers(&["main.rs", "docs/x.txt", "src/x.txt", "Makefile"]),
+            (vec!["@b", "@d"], vec![3])
+        );
+        // Sections may need several approvals, and optional sections need none
+        assert_eq!(
+            reviewers(&["README.md", "vendor/x.js", "vendor/x.txt"]),
+            (vec!["@x", "@y"], vec![2])
+        );
+        assert_eq!(reviewers(&[]), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_section_matches() {
+        let source = "
+/docs/ @writers
+
+[Docs][2] @docs-team
+*.md
+
+^[Frontend][3]
+*.js @frontend
+
+[Unowned]
+/docs/generated/
+";
+        let options = ParseOptions {
+            dialect: Dialect::GitLab,
+            ..Default::default()
+        };
+        let ruleset = parse_with_options(source, &options).into_ruleset();
+
+        let matches = |path| {
+            ruleset
+                .section_matches(path)
+                .into_iter()
+                .map(|m| {
+                    (
+                        m.section.map(|s| s.name.as_str()),
+                        m.rule_index,
+                        m.required_approvals,
+                        m.optional,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            matches("docs/README.md"),
+            vec![(None, 0, 1, false), (Some("Docs"), 1, 2, false)]
+        );
+        assert_eq!(
+            matches("docs/app.js"),
+            vec![(None, 0, 1, false), (Some("Frontend"), 2, 0, true)]
+        );
+        assert_eq!(
+            matches("docs/generated/api.txt"),
+            vec![(None, 0, 1, false), (Some("Unowned"), 3, 0, false)]
+        );
+    }
+
+    #[test]
+    fn test_owner_policies() {
+        use super::{OwnerKind, OwnerPolicy};
+        use std::sync::Arc;
+
+        let cases = [
+            ("@octocat", [Some(OwnerKind::User); 3]),
+            ("@org/team", [Some(OwnerKind::Team); 3]),
+            ("dev@example.engineering", [Some(OwnerKind::Email); 3]),
+            (
+                "@first.last",
+                [None, Some(OwnerKind::User), Some(OwnerKind::User)],
+            ),
+            (
+                "@org/my.team",
+                [None, Some(OwnerKind::Team), Some(OwnerKind::Team)],
+            ),
+            ("@org/group/subgroup", [None, None, Some(OwnerKind::Team)]),
+            ("octocat", [None; 3]),
+        ];
+        let policies = [
+            OwnerPolicy::GitHub,
+            OwnerPolicy::GitHubEnterprise,
+            OwnerPolicy::GitLab,
+        ];
+        for (owner, expected) in cases {
+            for (policy, expected) in policies.iter().zip(expected) {
+                assert_eq!(
+                    policy.owner_kind(owner),
+                    expected,
+                    "{} with {:?}",
+                    owner,
+                    policy
+                );
+            }
+        }
+
+        let options = ParseOptions {
+            owner_policy: OwnerPolicy::Custom(Arc::new(|owner| {
+                owner.starts_with('~').then_some(OwnerKind::User)
+            })),
+            ..Default::default()
+        };
+        let result = parse_with_options("*.rs ~alice @bob\n", &options);
+        assert_eq!(result.rules[0].owners.len(), 1);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].message, "invalid owner: @bob");
+    }
+}